@@ -0,0 +1,1526 @@
+//! Turns GIFs (and APNGs, animated WebPs and still images) into frames of colored
+//! braille cells that render as ratatui `Line`s. This is the pure conversion half of
+//! `gif_braille_tui`; the binary adds the terminal, playback and exports on top.
+
+use std::{
+    error::Error,
+    fs,
+    io::Cursor,
+    path::Path,
+    str::FromStr,
+    sync::LazyLock,
+    time::Duration,
+};
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+use image::{imageops, AnimationDecoder, ImageBuffer, ImageDecoder, Rgba, RgbaImage};
+use image::io::Limits;
+
+/// Default cap on a frame's width/height; anything larger is rejected before decoding.
+pub const DEFAULT_MAX_FRAME_DIM: u32 = 4096;
+
+/// How long each frame is shown unless overridden.
+pub const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(96);
+/// Native delays this short are treated as unset and replaced, as browsers do.
+const MIN_NATIVE_DELAY: Duration = Duration::from_millis(10);
+const UNSET_NATIVE_DELAY: Duration = Duration::from_millis(100);
+
+/// Holds the braille + color lines for a single frame and how long it stays on screen.
+pub struct BrailleFrame<'a> {
+    pub lines: Vec<Line<'a>>,
+    pub delay: Duration,
+    /// `(row, col)` of the cells `--focus-peak` highlights; empty unless it is enabled.
+    pub peaks: Vec<(usize, usize)>,
+}
+
+/// Everything that controls how one image becomes colored cells, independent of where the
+/// frames come from. The defaults convert the image as is, one pixel per dot.
+#[derive(Clone)]
+pub struct ConvertOptions {
+    pub mode: RenderMode,
+    pub tone_map: ToneMap,
+    /// Keep thin, dim lines visible by lighting high-contrast sub-pixels.
+    pub preserve_edges: bool,
+    /// Give every cell a background color from its unlit dots.
+    pub two_tone: bool,
+    /// Hue (degrees) to keep in color; every other cell is drawn in grayscale.
+    pub keep_hue: Option<f32>,
+    /// How far (degrees) a cell's hue may be from `keep_hue` and still keep its color.
+    pub hue_range: f32,
+    /// Only these dot bits may ever be lit.
+    pub dot_mask: u8,
+    /// Map every cell color onto these colors.
+    pub palette: Option<Palette>,
+    /// Blend between the two nearest palette colors instead of snapping to one.
+    pub palette_blend: bool,
+    /// Gaussian blur sigma per unit of downscale, applied before resizing.
+    pub prescale_blur: Option<f32>,
+    pub checker: bool,
+    /// Resolution of the dot and color sampling buffers relative to 2×4 pixels per cell.
+    pub dot_scale: f32,
+    pub color_scale: f32,
+    /// Minimum luminance (0..=255) for a dot to light up.
+    pub threshold: f32,
+    /// Minimum alpha for a dot to light up.
+    pub alpha_threshold: u8,
+    /// Resampling filter used when fitting the image to `max_cells`.
+    pub filter: imageops::FilterType,
+    pub color_mode: ColorMode,
+    pub cell_color: CellColor,
+    /// Reverse the cell order of every line for right-to-left terminals.
+    pub rtl: bool,
+    /// Fit the image into this many cell columns × rows first (never upscaling).
+    pub max_cells: Option<(u32, u32)>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            mode: RenderMode::Braille,
+            tone_map: ToneMap::None,
+            preserve_edges: false,
+            two_tone: false,
+            keep_hue: None,
+            hue_range: 30.0,
+            dot_mask: 0xFF,
+            palette: None,
+            palette_blend: false,
+            prescale_blur: None,
+            checker: false,
+            dot_scale: 1.0,
+            color_scale: 1.0,
+            threshold: 20.0,
+            alpha_threshold: 50,
+            filter: imageops::FilterType::Lanczos3,
+            color_mode: ColorMode::Truecolor,
+            cell_color: CellColor::Average,
+            rtl: false,
+            max_cells: None,
+        }
+    }
+}
+
+/// Tone-mapping operator applied to source pixels before conversion.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    None,
+    Reinhard,
+    Aces,
+}
+
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ToneMap::None),
+            "reinhard" => Ok(ToneMap::Reinhard),
+            "aces" => Ok(ToneMap::Aces),
+            _ => Err(format!("unknown tone map: {s}")),
+        }
+    }
+}
+
+/// Which glyphs each cell is drawn with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// 2×4 braille dots per cell.
+    Braille,
+    /// A solid block per cell, colored by the cell's average.
+    Blocks,
+    /// 2×2 quadrant blocks with separate foreground and background colors.
+    Quadrant,
+}
+
+impl FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "braille" => Ok(RenderMode::Braille),
+            "blocks" => Ok(RenderMode::Blocks),
+            "quadrant" => Ok(RenderMode::Quadrant),
+            _ => Err(format!("unknown mode: {s}")),
+        }
+    }
+}
+
+/// How a cell's single color is derived from its pixels.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CellColor {
+    /// Flat average of every pixel.
+    Average,
+    /// Gaussian-weighted toward the middle of the cell, for crisper color edges.
+    CenterWeighted,
+    /// The most common color.
+    Dominant,
+    /// Average of the pixels whose dots are lit.
+    Lit,
+}
+
+impl FromStr for CellColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(CellColor::Average),
+            "center-weighted" => Ok(CellColor::CenterWeighted),
+            "dominant" => Ok(CellColor::Dominant),
+            "lit" => Ok(CellColor::Lit),
+            _ => Err(format!("unknown --cell-color method: {s}")),
+        }
+    }
+}
+
+/// How cell colors reach the terminal (`--colors`); cycled live with `c`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Truecolor,
+    /// Nearest entry of the xterm 256-color cube and gray ramp.
+    Ansi256,
+    /// Nearest of the 16 standard ANSI colors, for the most basic terminals.
+    Ansi16,
+    Gray,
+    /// No colors at all, just the terminal's default foreground.
+    Mono,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Truecolor => ColorMode::Ansi256,
+            ColorMode::Ansi256 => ColorMode::Ansi16,
+            ColorMode::Ansi16 => ColorMode::Gray,
+            ColorMode::Gray => ColorMode::Mono,
+            ColorMode::Mono => ColorMode::Truecolor,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Truecolor => "truecolor",
+            ColorMode::Ansi256 => "256",
+            ColorMode::Ansi16 => "16",
+            ColorMode::Gray => "gray",
+            ColorMode::Mono => "mono",
+        }
+    }
+
+    /// Re-encodes a truecolor cell color for this mode.
+    pub fn apply(self, color: Color) -> Option<Color> {
+        let [r, g, b] = color_to_rgb(color);
+        match self {
+            ColorMode::Truecolor => Some(color),
+            ColorMode::Ansi256 => Some(Color::Indexed(nearest_ansi256(r, g, b))),
+            ColorMode::Ansi16 => Some(nearest_ansi16(r, g, b)),
+            ColorMode::Gray => {
+                let v = luminance(r, g, b).round().min(255.0) as u8;
+                Some(Color::Rgb(v, v, v))
+            }
+            ColorMode::Mono => None,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorMode::Truecolor),
+            "256" => Ok(ColorMode::Ansi256),
+            "16" => Ok(ColorMode::Ansi16),
+            "gray" => Ok(ColorMode::Gray),
+            "mono" => Ok(ColorMode::Mono),
+            _ => Err(format!("unknown --colors mode: {s}")),
+        }
+    }
+}
+
+/// A user-supplied set of colors every cell color gets mapped onto.
+#[derive(Clone)]
+pub struct Palette(Vec<[u8; 3]>);
+
+impl Palette {
+    /// The nearest palette color, or with `blend` a mix of the two nearest weighted by
+    /// how close each one is, which avoids hard banding on gradients.
+    pub fn map(&self, color: Color, blend: bool) -> Color {
+        let rgb = color_to_rgb(color);
+        let distance = |p: &[u8; 3]| {
+            p.iter().zip(&rgb).map(|(&a, &b)| (a as f32 - b as f32).powi(2)).sum::<f32>().sqrt()
+        };
+        let mut ranked: Vec<(f32, [u8; 3])> = self.0.iter().map(|p| (distance(p), *p)).collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mixed = match ranked.as_slice() {
+            [] => rgb,
+            [(d1, c1), (d2, c2), ..] if blend && *d1 > 0.0 => {
+                let w1 = d2 / (d1 + d2);
+                let mut out = [0u8; 3];
+                for i in 0..3 {
+                    out[i] = (c1[i] as f32 * w1 + c2[i] as f32 * (1.0 - w1)).round() as u8;
+                }
+                out
+            }
+            [(_, c1), ..] => *c1,
+        };
+        Color::Rgb(mixed[0], mixed[1], mixed[2])
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colors = s
+            .split(',')
+            .map(|hex| {
+                let hex = hex.trim().trim_start_matches('#');
+                match u32::from_str_radix(hex, 16) {
+                    Ok(v) if hex.len() == 6 => Ok([(v >> 16) as u8, (v >> 8) as u8, v as u8]),
+                    _ => Err(format!("bad palette color: {hex}")),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Palette(colors))
+    }
+}
+
+/// The bit of each braille dot, indexed `[column][row]`, in the Unicode order terminals
+/// draw them: dots 1-3 and 7 down the left column, 4-6 and 8 down the right.
+pub const BRAILLE_DOT_BITS: [[u8; 4]; 2] = [[0, 1, 2, 6], [3, 4, 5, 7]];
+
+/// The braille character with exactly these dots raised, in Unicode bit order (see
+/// `BRAILLE_DOT_BITS`).
+pub fn braille_glyph(dots: u8) -> char {
+    char::from_u32(0x2800 + dots as u32).unwrap_or(' ')
+}
+
+/// All 256 braille glyphs in dot order, so spans can borrow theirs for `'static` instead
+/// of allocating one string per cell.
+static BRAILLE_GLYPHS: LazyLock<String> = LazyLock::new(|| (0..=255).map(braille_glyph).collect());
+
+/// `braille_glyph` as a `'static` string slice.
+pub fn braille_str(dots: u8) -> &'static str {
+    // Every glyph in U+2800..=U+28FF is three bytes of UTF-8
+    let start = dots as usize * 3;
+    &BRAILLE_GLYPHS[start..start + 3]
+}
+
+/// The braille or quadrant glyph flipped left to right; anything else is symmetric enough
+/// to keep as is.
+pub fn mirror_glyph(glyph: &str) -> String {
+    let mut chars = glyph.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return glyph.to_string();
+    };
+    if let Some(dots) = (c as u32).checked_sub(0x2800).filter(|&d| d < 0x100) {
+        // Unicode dot order: left column bits 0, 1, 2, 6; right column bits 3, 4, 5, 7
+        let swap = |d: u32, a: u32, b: u32| {
+            let (x, y) = ((d >> a) & 1, (d >> b) & 1);
+            (d & !(1 << a) & !(1 << b)) | (x << b) | (y << a)
+        };
+        let flipped = swap(swap(swap(swap(dots, 0, 3), 1, 4), 2, 5), 6, 7);
+        return char::from_u32(0x2800 + flipped).map_or(glyph.to_string(), String::from);
+    }
+    match QUADRANT_GLYPHS.iter().position(|&q| q == glyph) {
+        Some(i) => {
+            let flipped = ((i & 0b0101) << 1) | ((i & 0b1010) >> 1);
+            QUADRANT_GLYPHS[flipped].to_string()
+        }
+        None => glyph.to_string(),
+    }
+}
+
+/// Converts one composited source frame, with `--focus-peak` cells picked out of the
+/// same fitted image the frame was converted from.
+pub fn convert_image(image: RgbaImage, opts: &ConvertOptions, focus_peak: Option<f32>) -> BrailleFrame<'static> {
+    let (image, resized) = fit_frame(image, opts);
+    let lines = convert_fitted(&image, &resized, opts);
+    let mut peaks = match focus_peak {
+        Some(top_percent) => peak_cells(&resized, top_percent),
+        None => Vec::new(),
+    };
+    if opts.rtl {
+        for (row, col) in &mut peaks {
+            *col = lines.get(*row).map_or(0, |line| line.spans.len().saturating_sub(*col + 1));
+        }
+    }
+    BrailleFrame {
+        lines,
+        delay: DEFAULT_FRAME_DELAY,
+        peaks,
+    }
+}
+
+/// Converts a single image into colored cell lines with the full set of conversion
+/// options: tone mapping, fitting into `max_cells`, separate dot and color sampling and
+/// the color mode.
+pub fn convert_frame(img: &RgbaImage, opts: &ConvertOptions) -> Vec<Line<'static>> {
+    let (image, resized) = fit_frame(img.clone(), opts);
+    convert_fitted(&image, &resized, opts)
+}
+
+/// `convert_frame` on what `fit_frame` returned, for callers that also need the fitted
+/// image (e.g. for `peak_cells`) without resizing twice.
+pub fn convert_fitted(image: &RgbaImage, resized: &RgbaImage, opts: &ConvertOptions) -> Vec<Line<'static>> {
+    let (new_width, new_height) = resized.dimensions();
+
+    // Separately sized dot and color buffers, when asked to sample either more finely or
+    // more coarsely than one pixel per dot
+    let rescaled = |scale: f32| {
+        (scale != 1.0 && new_width > 0 && new_height > 0).then(|| {
+            let w = ((new_width as f32 * scale).round() as u32).max(1);
+            let h = ((new_height as f32 * scale).round() as u32).max(1);
+            imageops::resize(image, w, h, opts.filter)
+        })
+    };
+    let dots_buffer = rescaled(opts.dot_scale);
+    let colors_buffer = rescaled(opts.color_scale);
+
+    // Convert to braille + color lines
+    let lines = rgba_to_braille_sampled(
+        dots_buffer.as_ref().unwrap_or(resized),
+        colors_buffer.as_ref().unwrap_or(resized),
+        resized.dimensions(),
+        opts,
+    );
+    match opts.color_mode {
+        ColorMode::Truecolor => lines,
+        mode => recolor_lines(&lines, mode),
+    }
+}
+
+/// Tone-maps (and with `--prescale-blur`, blurs) `image`, returning it together with a
+/// copy resized to fit `max_cells`.
+pub fn fit_frame(mut image: RgbaImage, opts: &ConvertOptions) -> (RgbaImage, RgbaImage) {
+    apply_tone_map(&mut image, opts.tone_map);
+    let (width, height) = image.dimensions();
+
+    // -- Keep aspect ratio --
+    let (new_width, new_height) = match opts.max_cells {
+        Some((cols, rows)) => compute_scaled_dims(width, height, cols * 2, rows * 4),
+        None => (width, height),
+    };
+
+    // Pre-blur in proportion to how far we shrink, so fine detail can't alias into moire
+    if let Some(sigma) = opts.prescale_blur.filter(|&sigma| sigma > 0.0) {
+        let factor = width as f32 / new_width.max(1) as f32;
+        if factor > 1.0 {
+            image = imageops::blur(&image, sigma * factor);
+        }
+    }
+
+    let resized = if (new_width, new_height) == (width, height) && width > 0 && height > 0 {
+        image.clone()
+    } else if new_width > 0 && new_height > 0 {
+        imageops::resize(
+            &image,
+            new_width,
+            new_height,
+            // Higher‐quality filter for smoother downscaling
+            opts.filter,
+        )
+    } else {
+        ImageBuffer::<Rgba<u8>, _>::new(1, 1)
+    };
+    (image, resized)
+}
+
+/// The cells whose edge energy (summed luminance gradient over their 2×4 pixels) is in
+/// the top `top_percent` percent of the frame.
+pub fn peak_cells(img: &RgbaImage, top_percent: f32) -> Vec<(usize, usize)> {
+    let (width, height) = img.dimensions();
+    let (cell_cols, cell_rows) = (width.div_ceil(2), height.div_ceil(4));
+    let lum = |x: u32, y: u32| {
+        let Rgba([r, g, b, _]) = *img.get_pixel(x, y);
+        luminance(r, g, b)
+    };
+
+    let mut energies = Vec::with_capacity((cell_cols * cell_rows) as usize);
+    for row in 0..cell_rows {
+        for col in 0..cell_cols {
+            let mut energy = 0.0;
+            for y in row * 4..(row * 4 + 4).min(height) {
+                for x in col * 2..(col * 2 + 2).min(width) {
+                    let here = lum(x, y);
+                    if x + 1 < width {
+                        energy += (lum(x + 1, y) - here).abs();
+                    }
+                    if y + 1 < height {
+                        energy += (lum(x, y + 1) - here).abs();
+                    }
+                }
+            }
+            energies.push((energy, row as usize, col as usize));
+        }
+    }
+
+    let keep = (energies.len() as f32 * top_percent.clamp(0.0, 100.0) / 100.0).round() as usize;
+    energies.sort_by(|a, b| b.0.total_cmp(&a.0));
+    energies
+        .into_iter()
+        .take(keep)
+        // Flat frames have no detail to point at
+        .filter(|&(energy, ..)| energy > 0.0)
+        .map(|(_, row, col)| (row, col))
+        .collect()
+}
+
+/// Errors out if either dimension exceeds `max_dim`.
+pub fn check_frame_dims(width: u32, height: u32, max_dim: u32) -> Result<(), String> {
+    if width > max_dim || height > max_dim {
+        return Err(format!(
+            "frame is {width}x{height}, larger than the --max-frame-dim limit of {max_dim}"
+        ));
+    }
+    Ok(())
+}
+
+/// True for inputs played frame by frame: GIFs, APNGs and animated WebPs.
+pub fn is_animation(bytes: &[u8]) -> bool {
+    match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Gif) => true,
+        Ok(image::ImageFormat::Png) => PngDecoder::new(Cursor::new(bytes)).is_ok_and(|d| d.is_apng()),
+        Ok(image::ImageFormat::WebP) => WebPDecoder::new(Cursor::new(bytes)).is_ok_and(|d| d.has_animation()),
+        _ => false,
+    }
+}
+
+/// Opens an animation (see `is_animation`) for lazy, frame-by-frame decoding (each frame
+/// composited onto the full canvas), enforcing `--max-frame-dim` before anything is
+/// allocated (and rejecting larger frames at all).
+pub fn open_animation(bytes: Vec<u8>, max_dim: u32) -> Result<image::Frames<'static>, Box<dyn Error>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dim);
+    limits.max_image_height = Some(max_dim);
+
+    // Refuse oversized canvases up front, before any frame buffer is allocated
+    match image::guess_format(&bytes)? {
+        image::ImageFormat::Png => {
+            let mut decoder = PngDecoder::new(Cursor::new(bytes))?;
+            let (canvas_w, canvas_h) = decoder.dimensions();
+            check_frame_dims(canvas_w, canvas_h, max_dim)?;
+            decoder.set_limits(limits)?;
+            Ok(decoder.apng().into_frames())
+        }
+        image::ImageFormat::WebP => {
+            let mut decoder = WebPDecoder::new(Cursor::new(bytes))?;
+            let (canvas_w, canvas_h) = decoder.dimensions();
+            check_frame_dims(canvas_w, canvas_h, max_dim)?;
+            decoder.set_limits(limits)?;
+            Ok(decoder.into_frames())
+        }
+        _ => {
+            let mut decoder = GifDecoder::new(Cursor::new(bytes))?;
+            let (canvas_w, canvas_h) = decoder.dimensions();
+            check_frame_dims(canvas_w, canvas_h, max_dim)?;
+            decoder.set_limits(limits)?;
+            Ok(decoder.into_frames())
+        }
+    }
+}
+
+/// A decoder's frame delay as a `Duration`. `Delay` keeps each format's own timing model
+/// exactly (GIF centiseconds, APNG `delay_num / delay_den` seconds, WebP milliseconds)
+/// as a ratio of milliseconds, so convert from that ratio rather than assuming any one.
+/// 0 ms and other near-zero delays fall back to 100 ms so a malformed file can't spin.
+pub fn native_delay(delay: image::Delay) -> Duration {
+    let (numer, denom) = delay.numer_denom_ms();
+    match Duration::from_nanos(numer as u64 * 1_000_000 / denom.max(1) as u64) {
+        delay if delay <= MIN_NATIVE_DELAY => UNSET_NATIVE_DELAY,
+        delay => delay,
+    }
+}
+
+/// What a quick structural scan of a GIF file reveals.
+pub struct GifInfo {
+    pub frames: usize,
+    /// Repeat count from the Netscape application extension (0 = forever), if present.
+    pub loop_count: Option<u16>,
+}
+
+impl GifInfo {
+    /// How many times a browser would play the animation, `None` meaning forever.
+    pub fn plays(&self) -> Option<u32> {
+        match self.loop_count {
+            Some(0) => None,
+            // The count is extra repeats after the first play
+            Some(n) => Some(n as u32 + 1),
+            None => Some(1),
+        }
+    }
+}
+
+/// Walks a GIF's block structure, counting image descriptors and picking out the loop
+/// count, without decompressing any pixel data. Stops at the first malformed block.
+pub fn scan_gif(bytes: &[u8]) -> GifInfo {
+    // Skips a color table if the packed field says one follows
+    let color_table_len = |packed: u8| if packed & 0x80 != 0 { 3 << ((packed & 0x07) + 1) } else { 0 };
+    // Skips a chain of data sub-blocks, returning the position after the terminator
+    let skip_sub_blocks = |mut pos: usize| {
+        while let Some(&len) = bytes.get(pos) {
+            pos += 1 + len as usize;
+            if len == 0 {
+                return Some(pos);
+            }
+        }
+        None
+    };
+
+    let mut info = GifInfo { frames: 0, loop_count: None };
+    let Some(&packed) = bytes.get(10) else {
+        return info;
+    };
+    let mut pos = 13 + color_table_len(packed);
+    while let Some(&block) = bytes.get(pos) {
+        let next = match block {
+            // Extension: introducer, label, sub-blocks. The Netscape application extension
+            // carries the loop count in a 3-byte sub-block: 0x01, count (little endian)
+            0x21 => {
+                let app_id = bytes.get(pos + 3..pos + 14);
+                if bytes.get(pos + 1) == Some(&0xFF) && matches!(app_id, Some(b"NETSCAPE2.0" | b"ANIMEXTS1.0")) {
+                    if let Some(&[3, 1, lo, hi]) = bytes.get(pos + 14..pos + 18) {
+                        info.loop_count = Some(u16::from_le_bytes([lo, hi]));
+                    }
+                }
+                skip_sub_blocks(pos + 2)
+            }
+            // Image descriptor, optional local color table, LZW code size, sub-blocks
+            0x2C => {
+                info.frames += 1;
+                bytes
+                    .get(pos + 9)
+                    .and_then(|&packed| skip_sub_blocks(pos + 10 + color_table_len(packed) + 1))
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => pos = next,
+            None => break,
+        }
+    }
+    info
+}
+
+/// Decodes a still image (PNG, JPEG, ...) no larger than `max_dim` either way, turning
+/// JPEGs upright according to their EXIF orientation tag.
+pub fn open_still(bytes: &[u8], max_dim: u32) -> Result<RgbaImage, Box<dyn Error>> {
+    let mut reader = image::io::Reader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dim);
+    limits.max_image_height = Some(max_dim);
+    reader.limits(limits);
+    let is_jpeg = reader.format() == Some(image::ImageFormat::Jpeg);
+
+    let image = reader.decode()?.into_rgba8();
+    let orientation = if is_jpeg { jpeg_orientation(bytes) } else { None };
+    Ok(apply_orientation(image, orientation.unwrap_or(1)))
+}
+
+/// The EXIF orientation (1..=8) from a JPEG's APP1 segment, if it has one.
+fn jpeg_orientation(bytes: &[u8]) -> Option<u16> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    // Walk the marker segments up to the start of the image data
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() && bytes[pos] == 0xFF {
+        let marker = bytes[pos + 1];
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let data = bytes.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xE1 {
+            if let Some(tiff) = data.strip_prefix(b"Exif\0\0") {
+                return tiff_orientation(tiff);
+            }
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// Looks up the orientation tag (0x0112) in the first IFD of a TIFF/EXIF block.
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let b = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    };
+    let u32_at = |at: usize| {
+        let b = [*tiff.get(at)?, *tiff.get(at + 1)?, *tiff.get(at + 2)?, *tiff.get(at + 3)?];
+        Some(if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    (0..entries)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+/// Rotates/flips `image` so that an image stored with EXIF `orientation` displays upright.
+fn apply_orientation(image: RgbaImage, orientation: u16) -> RgbaImage {
+    match orientation {
+        2 => imageops::flip_horizontal(&image),
+        3 => imageops::rotate180(&image),
+        4 => imageops::flip_vertical(&image),
+        5 => imageops::flip_horizontal(&imageops::rotate90(&image)),
+        6 => imageops::rotate90(&image),
+        7 => imageops::flip_horizontal(&imageops::rotate270(&image)),
+        8 => imageops::rotate270(&image),
+        _ => image,
+    }
+}
+
+/// Remaps every channel through `op` in linear light, normalized so white stays white.
+pub fn apply_tone_map(img: &mut RgbaImage, op: ToneMap) {
+    if op == ToneMap::None {
+        return;
+    }
+
+    let curve = |x: f32| match op {
+        ToneMap::None => x,
+        ToneMap::Reinhard => x / (1.0 + x),
+        // Narkowicz's fit of the ACES filmic curve
+        ToneMap::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+    };
+    let white = curve(1.0);
+    let lut: Vec<u8> = (0..=255u8)
+        .map(|v| linear_to_srgb(curve(srgb_to_linear(v)) / white))
+        .collect();
+    for Rgba([r, g, b, _]) in img.pixels_mut() {
+        *r = lut[*r as usize];
+        *g = lut[*g as usize];
+        *b = lut[*b as usize];
+    }
+}
+
+/// Decodes an sRGB channel value to linear light in 0.0..=1.0.
+pub fn srgb_to_linear(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Encodes linear light (clamped to 0.0..=1.0) back to an sRGB channel value.
+pub fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (encoded * 255.0).round() as u8
+}
+
+/// Compute new dimensions for the image, preserving aspect ratio,
+/// so it fits within (max_w, max_h).
+pub fn compute_scaled_dims(
+    orig_w: u32,
+    orig_h: u32,
+    max_w: u32,
+    max_h: u32,
+) -> (u32, u32) {
+    compute_scaled_dims_checked(orig_w, orig_h, max_w, max_h).0
+}
+
+/// `compute_scaled_dims`, also saying whether a side that would have rounded to 0 px was
+/// clamped to 1 px, which stretches very thin content out of proportion.
+pub fn compute_scaled_dims_checked(
+    orig_w: u32,
+    orig_h: u32,
+    max_w: u32,
+    max_h: u32,
+) -> ((u32, u32), bool) {
+    if max_w == 0 || max_h == 0 || orig_w == 0 || orig_h == 0 {
+        return ((0, 0), false);
+    }
+
+    let orig_w_f = orig_w as f32;
+    let orig_h_f = orig_h as f32;
+    let max_w_f = max_w as f32;
+    let max_h_f = max_h as f32;
+
+    // scale factor to fit width
+    let scale_w = max_w_f / orig_w_f;
+    // scale factor to fit height
+    let scale_h = max_h_f / orig_h_f;
+    let scale = scale_w.min(scale_h);
+
+    // if the image is smaller already, skip upscaling
+    let scale = scale.min(1.0);
+
+    let (new_w, new_h) = ((orig_w_f * scale).round(), (orig_h_f * scale).round());
+    let clamped = new_w < 1.0 || new_h < 1.0;
+    ((new_w.max(1.0) as u32, new_h.max(1.0) as u32), clamped)
+}
+
+/// The two shades of the `--checker` pattern behind transparent cells.
+const CHECKER_DARK: Color = Color::Rgb(38, 38, 38);
+const CHECKER_LIGHT: Color = Color::Rgb(58, 58, 58);
+
+/// Glyph used for every cell in `--mode blocks`.
+const FULL_BLOCK: &str = QUADRANT_GLYPHS[15];
+
+/// Minimum amount a sub-pixel must outshine its neighbours to count as a thin edge.
+const EDGE_CONTRAST: f32 = 8.0;
+
+/// Running RGB total used to average a group of pixels.
+#[derive(Clone, Copy, Default)]
+pub struct ColorSum {
+    r: u32,
+    g: u32,
+    b: u32,
+    count: u32,
+}
+
+impl ColorSum {
+    pub fn add(&mut self, r: u8, g: u8, b: u8) {
+        self.r += r as u32;
+        self.g += g as u32;
+        self.b += b as u32;
+        self.count += 1;
+    }
+
+    /// Adds a pixel counting `weight` times.
+    fn add_weighted(&mut self, r: u8, g: u8, b: u8, weight: u32) {
+        self.r += r as u32 * weight;
+        self.g += g as u32 * weight;
+        self.b += b as u32 * weight;
+        self.count += weight;
+    }
+
+    fn merge(&mut self, other: &ColorSum) {
+        self.r += other.r;
+        self.g += other.g;
+        self.b += other.b;
+        self.count += other.count;
+    }
+
+    /// Average color, or `None` if nothing was added.
+    pub fn average(&self) -> Option<Color> {
+        match self.count {
+            0 => None,
+            n => Some(Color::Rgb((self.r / n) as u8, (self.g / n) as u8, (self.b / n) as u8)),
+        }
+    }
+}
+
+/// Convert an RGBA image into multi‐line braille cells with 24‐bit color, one cell per
+/// 2×4 pixels, using the default `ConvertOptions`.
+pub fn rgba_to_braille_colored(img: &RgbaImage) -> Vec<Line<'static>> {
+    rgba_to_braille_sampled(img, img, img.dimensions(), &ConvertOptions::default())
+}
+
+/// Converts with the full set of `ConvertOptions`, dots and colors sampled from separate
+/// buffers (`--dot-scale`, `--color-scale`). `grid` is the size, in 2×4-per-cell pixels,
+/// of the cell grid; each buffer may be any resolution covering the same picture, and
+/// every sub-pixel of the grid maps onto the matching block of each buffer.
+///
+/// `--mode blocks` swaps the braille glyphs for solid blocks of the cell's average color;
+/// `--mode quadrant` uses quadrant blocks with a foreground and background color per cell.
+/// With `--preserve-edges`, dim sub-pixels that stand out sharply from their neighbours
+/// (e.g. 1px lines softened by downscaling) still light their dot. With `--two-tone`,
+/// each cell's foreground comes from its lit dots and its background from the unlit ones.
+pub fn rgba_to_braille_sampled(dots_img: &RgbaImage, colors_img: &RgbaImage, grid: (u32, u32), opts: &ConvertOptions) -> Vec<Line<'static>> {
+    let (width, height) = grid;
+    let dots_map = BlockMap::new(grid, dots_img.dimensions());
+    let colors_map = BlockMap::new(grid, colors_img.dimensions());
+
+    // Each braille cell is 2 px wide, 4 px tall
+    let cell_cols = width.div_ceil(2);
+    let cell_rows = height.div_ceil(4);
+
+    let mut lines = Vec::with_capacity(cell_rows as usize);
+
+    for row in 0..cell_rows {
+        let mut span_vec = Vec::with_capacity(cell_cols as usize);
+
+        for col in 0..cell_cols {
+            let mut all = ColorSum::default();
+            let mut lit = ColorSum::default();
+            let mut unlit = ColorSum::default();
+            // 1×2 px halves of the cell for `--mode quadrant`: TL, TR, BL, BR
+            let mut quads = [ColorSum::default(); 4];
+            let mut dots: u8 = 0;
+            let mut transparent = true;
+            // For the other `--cell-color` methods
+            let mut weighted = ColorSum::default();
+            let mut pixels = [[0u8; 3]; 8];
+            let mut pixel_count = 0;
+
+            for sub_row in 0..4 {
+                for sub_col in 0..2 {
+                    let px_x = col * 2 + sub_col;
+                    let px_y = row * 4 + sub_row;
+
+                    if px_x < width && px_y < height {
+                        // Color: the average of this sub-pixel's block in the color buffer
+                        let mut block = ColorSum::default();
+                        for (x, y) in colors_map.block(px_x, px_y) {
+                            let Rgba([r, g, b, _]) = *colors_img.get_pixel(x, y);
+                            block.add(r, g, b);
+                        }
+                        let [r, g, b] = block.average().map_or([0, 0, 0], color_to_rgb);
+
+                        let bit_index = BRAILLE_DOT_BITS[sub_col as usize][sub_row as usize];
+
+                        // Dot: simple brightness threshold, by majority over its block in the
+                        // dot buffer
+                        let (mut lit_px, mut total_px) = (0, 0);
+                        for (x, y) in dots_map.block(px_x, px_y) {
+                            let Rgba([r, g, b, a]) = *dots_img.get_pixel(x, y);
+                            let lum = luminance(r, g, b);
+                            transparent &= a == 0;
+                            if a > opts.alpha_threshold && (lum > opts.threshold || (opts.preserve_edges && is_thin_edge(dots_img, x, y, lum))) {
+                                lit_px += 1;
+                            }
+                            total_px += 1;
+                        }
+                        if lit_px * 2 >= total_px && lit_px > 0 {
+                            dots |= 1 << bit_index;
+                            lit.add(r, g, b);
+                        } else {
+                            unlit.add(r, g, b);
+                        }
+                        all.add(r, g, b);
+                        weighted.add_weighted(r, g, b, CENTER_WEIGHTS[sub_row as usize]);
+                        pixels[pixel_count] = [r, g, b];
+                        pixel_count += 1;
+                        quads[(sub_row / 2 * 2 + sub_col) as usize].add(r, g, b);
+                    }
+                }
+            }
+
+            let avg = match opts.cell_color {
+                CellColor::Average => all.average(),
+                CellColor::CenterWeighted => weighted.average(),
+                CellColor::Dominant => dominant_color(&pixels[..pixel_count]),
+                CellColor::Lit => lit.average().or(all.average()),
+            }
+            .unwrap_or(Color::Rgb(0, 0, 0));
+
+            // Selective color: cells outside the kept hue range turn gray, then everything
+            // is mapped onto the custom palette if there is one
+            let keep_color = opts
+                .keep_hue
+                .is_none_or(|target| hue_distance(avg, target).is_some_and(|d| d <= opts.hue_range));
+            let finish = |c: Color| {
+                let c = if keep_color { c } else { to_grayscale(c) };
+                match &opts.palette {
+                    Some(palette) => palette.map(c, opts.palette_blend),
+                    None => c,
+                }
+            };
+
+            let (content, style) = match opts.mode {
+                // Transparency shown like an image editor does, as dim alternating squares
+                _ if opts.checker && transparent => {
+                    let shade = if (row + col) % 2 == 0 { CHECKER_DARK } else { CHECKER_LIGHT };
+                    (FULL_BLOCK, Style::default().fg(shade))
+                }
+                // Plain color mosaic: one solid block per cell, no dot pattern
+                RenderMode::Blocks => (FULL_BLOCK, Style::default().fg(finish(avg))),
+                RenderMode::Quadrant => {
+                    let (glyph, fg, bg) = quadrant_cell(&quads);
+                    let style = Style::default().fg(finish(fg.unwrap_or(avg)));
+                    (glyph, bg.map_or(style, |bg| style.bg(finish(bg))))
+                }
+                RenderMode::Braille => {
+                    let dots = dots & opts.dot_mask;
+                    let content = braille_str(dots);
+
+                    // Create a colored span
+                    let style = if opts.two_tone {
+                        let style = Style::default().fg(finish(lit.average().unwrap_or(avg)));
+                        match unlit.average() {
+                            Some(bg) => style.bg(finish(bg)),
+                            None => style,
+                        }
+                    } else {
+                        Style::default().fg(finish(avg))
+                    };
+                    (content, style)
+                }
+            };
+            let span = Span::styled(content, style.add_modifier(Modifier::BOLD));
+            span_vec.push(span);
+        }
+
+        // Right-to-left terminals lay cells out from the right edge
+        if opts.rtl {
+            span_vec.reverse();
+        }
+        lines.push(Line::from(span_vec));
+    }
+
+    lines
+}
+
+/// Per-row weights of the small Gaussian kernel `--cell-color center-weighted` uses over a
+/// cell's four pixel rows (both columns are equally central).
+const CENTER_WEIGHTS: [u32; 4] = [1, 3, 3, 1];
+
+/// The average of the most common color among `pixels`, with colors grouped by their top
+/// three bits per channel so near-identical shades count together.
+fn dominant_color(pixels: &[[u8; 3]]) -> Option<Color> {
+    let bucket = |p: &[u8; 3]| p.map(|c| c >> 5);
+    let most_common = pixels
+        .iter()
+        .map(bucket)
+        .max_by_key(|b| pixels.iter().filter(|p| bucket(p) == *b).count())?;
+    let mut sum = ColorSum::default();
+    for p in pixels.iter().filter(|p| bucket(p) == most_common) {
+        sum.add(p[0], p[1], p[2]);
+    }
+    sum.average()
+}
+
+/// Maps each pixel of the 2×4-per-cell grid onto the block of pixels covering the same
+/// area in a buffer of another resolution (at least one pixel, even when coarser).
+struct BlockMap {
+    grid: (u32, u32),
+    buffer: (u32, u32),
+}
+
+impl BlockMap {
+    fn new(grid: (u32, u32), buffer: (u32, u32)) -> Self {
+        BlockMap { grid, buffer }
+    }
+
+    fn block(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> {
+        let span = |i: u32, grid: u32, buffer: u32| {
+            let start = (i as u64 * buffer as u64 / grid as u64) as u32;
+            let end = ((i as u64 + 1) * buffer as u64 / grid as u64) as u32;
+            let start = start.min(buffer.saturating_sub(1));
+            start..end.clamp(start + 1, buffer.max(1))
+        };
+        let xs = span(x, self.grid.0, self.buffer.0);
+        span(y, self.grid.1, self.buffer.1).flat_map(move |y| xs.clone().map(move |x| (x, y)))
+    }
+}
+
+/// Quadrant block glyphs indexed by which quadrants are filled (bit 0 = top left,
+/// 1 = top right, 2 = bottom left, 3 = bottom right).
+pub const QUADRANT_GLYPHS: [&str; 16] = [
+    " ", "\u{2598}", "\u{259D}", "\u{2580}", "\u{2596}", "\u{258C}", "\u{259E}", "\u{259B}",
+    "\u{2597}", "\u{259A}", "\u{2590}", "\u{259C}", "\u{2584}", "\u{2599}", "\u{259F}", "\u{2588}",
+];
+
+/// Splits a cell's four quadrants into foreground (brighter than the cell's mean) and
+/// background, returning the matching glyph and each side's average color.
+fn quadrant_cell(quads: &[ColorSum; 4]) -> (&'static str, Option<Color>, Option<Color>) {
+    let lums = quads.map(|quad| {
+        quad.average().map(|c| {
+            let [r, g, b] = color_to_rgb(c);
+            luminance(r, g, b)
+        })
+    });
+    let (sum, n) = lums.iter().flatten().fold((0.0, 0), |(sum, n), &l| (sum + l, n + 1));
+    let mean = if n > 0 { sum / n as f32 } else { 0.0 };
+
+    let mut mask = 0;
+    let (mut fg, mut bg) = (ColorSum::default(), ColorSum::default());
+    for (i, (quad, lum)) in quads.iter().zip(lums).enumerate() {
+        match lum {
+            Some(lum) if lum > mean => {
+                mask |= 1 << i;
+                fg.merge(quad);
+            }
+            _ => bg.merge(quad),
+        }
+    }
+    // A flat cell has nothing above its mean; draw it as a solid block instead of a blank
+    if mask == 0 {
+        return (QUADRANT_GLYPHS[15], bg.average(), None);
+    }
+    (QUADRANT_GLYPHS[mask], fg.average(), bg.average())
+}
+
+/// Angular distance in degrees between `color`'s hue and `target`, or `None` for grays.
+fn hue_distance(color: Color, target: f32) -> Option<f32> {
+    let [r, g, b] = color_to_rgb(color).map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    if delta == 0.0 {
+        return None;
+    }
+
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let diff = (hue - target).rem_euclid(360.0);
+    Some(diff.min(360.0 - diff))
+}
+
+/// Same brightness as `color`, with the saturation removed.
+pub fn to_grayscale(color: Color) -> Color {
+    let [r, g, b] = color_to_rgb(color);
+    let lum = luminance(r, g, b).round() as u8;
+    Color::Rgb(lum, lum, lum)
+}
+
+/// Rec. 709 luma of an sRGB color, 0..=255.
+fn luminance(r: u8, g: u8, b: u8) -> f32 {
+    0.2126 * (r as f32) + 0.7152 * (g as f32) + 0.0722 * (b as f32)
+}
+
+/// True when the pixel at (`x`, `y`) is a local brightness ridge: brighter than every
+/// neighbour on one axis and clearly above their average.
+fn is_thin_edge(img: &RgbaImage, x: u32, y: u32, lum: f32) -> bool {
+    let (w, h) = img.dimensions();
+    let lum_at = |nx: Option<u32>, ny: Option<u32>| {
+        let (nx, ny) = (nx?, ny?);
+        (nx < w && ny < h).then(|| {
+            let Rgba([r, g, b, _]) = *img.get_pixel(nx, ny);
+            luminance(r, g, b)
+        })
+    };
+    let is_ridge = |neighbours: [Option<f32>; 2]| {
+        let (sum, n) = neighbours.iter().flatten().fold((0.0, 0), |(sum, n), &v| (sum + v, n + 1));
+        n > 0 && neighbours.iter().flatten().all(|&v| lum > v) && lum - sum / n as f32 >= EDGE_CONTRAST
+    };
+
+    is_ridge([lum_at(x.checked_sub(1), Some(y)), lum_at(Some(x + 1), Some(y))])
+        || is_ridge([lum_at(Some(x), y.checked_sub(1)), lum_at(Some(x), Some(y + 1))])
+}
+
+/// Best-effort RGB value for a ratatui color.
+pub fn color_to_rgb(color: Color) -> [u8; 3] {
+    if let Some(&(_, rgb)) = ANSI16.iter().find(|(named, _)| *named == color) {
+        return rgb;
+    }
+    match color {
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::Indexed(i @ 0..=15) => ANSI16[i as usize].1,
+        Color::Indexed(i @ 16..=231) => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            [level(i / 36), level(i / 6 % 6), level(i % 6)]
+        }
+        Color::Indexed(i @ 232..=255) => [8 + (i - 232) * 10; 3],
+        _ => [255, 255, 255],
+    }
+}
+
+/// The 16 standard ANSI colors in palette order (0..=15), with xterm's default RGB values.
+pub const ANSI16: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::Red, [205, 0, 0]),
+    (Color::Green, [0, 205, 0]),
+    (Color::Yellow, [205, 205, 0]),
+    (Color::Blue, [0, 0, 238]),
+    (Color::Magenta, [205, 0, 205]),
+    (Color::Cyan, [0, 205, 205]),
+    (Color::Gray, [229, 229, 229]),
+    (Color::DarkGray, [127, 127, 127]),
+    (Color::LightRed, [255, 0, 0]),
+    (Color::LightGreen, [0, 255, 0]),
+    (Color::LightYellow, [255, 255, 0]),
+    (Color::LightBlue, [92, 92, 255]),
+    (Color::LightMagenta, [255, 0, 255]),
+    (Color::LightCyan, [0, 255, 255]),
+    (Color::White, [255, 255, 255]),
+];
+
+/// The standard ANSI color closest to an RGB color.
+pub fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    let distance = |c: &[u8; 3]| -> i32 {
+        [r, g, b].iter().zip(c).map(|(&a, &b)| (a as i32 - b as i32).pow(2)).sum()
+    };
+    ANSI16.iter().min_by_key(|(_, rgb)| distance(rgb)).map_or(Color::White, |&(color, _)| color)
+}
+
+/// The xterm 256-color index closest to an RGB color, picking between the 6×6×6 cube
+/// (16..=231) and the 24-step gray ramp (232..=255).
+pub fn nearest_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..CUBE.len()).min_by_key(|&i| (CUBE[i] as i32 - v as i32).abs()).unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = [CUBE[ri], CUBE[gi], CUBE[bi]];
+
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let step = (avg.saturating_sub(3) / 10).min(23);
+    let gray = (8 + step * 10) as u8;
+
+    let distance = |c: [u8; 3]| -> i32 {
+        [r, g, b].iter().zip(&c).map(|(&a, &b)| (a as i32 - b as i32).pow(2)).sum()
+    };
+    if distance([gray; 3]) < distance(cube) {
+        232 + step as u8
+    } else {
+        16 + (36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+/// Copies of `frames` with every cell color re-encoded for `mode`.
+pub fn recolor_frames(frames: &[BrailleFrame<'static>], mode: ColorMode) -> Vec<BrailleFrame<'static>> {
+    frames
+        .iter()
+        .map(|frame| BrailleFrame {
+            lines: recolor_lines(&frame.lines, mode),
+            delay: frame.delay,
+            peaks: frame.peaks.clone(),
+        })
+        .collect()
+}
+
+pub fn recolor_lines(lines: &[Line<'static>], mode: ColorMode) -> Vec<Line<'static>> {
+    lines
+        .iter()
+        .map(|line| {
+            Line::from(
+                line.spans
+                    .iter()
+                    .map(|span| {
+                        let mut span = span.clone();
+                        span.style.fg = span.style.fg.and_then(|c| mode.apply(c));
+                        span.style.bg = span.style.bg.and_then(|c| mode.apply(c));
+                        span
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Decodes a GIF (or APNG, animated WebP or still image) and converts every frame, each
+/// fitted into `max_cols` × `max_rows` cells with the default `ConvertOptions` and shown
+/// for its native delay. Nothing here asks the terminal; pick the size to render into.
+pub fn convert_gif(path: &Path, max_cols: u32, max_rows: u32) -> Result<Vec<BrailleFrame<'static>>, Box<dyn Error>> {
+    let opts = ConvertOptions { max_cells: Some((max_cols, max_rows)), ..ConvertOptions::default() };
+    frames(path, DEFAULT_MAX_FRAME_DIM, &opts).collect()
+}
+
+/// `convert_gif` for any `ConvertOptions` and frames no larger than `max_frame_dim` either
+/// way, calling `on_frame(converted, total)` after each frame so embedders can show their
+/// own progress. `total` is 0 when the file doesn't say how many frames it has (APNG, WebP).
+pub fn convert_gif_with_progress(
+    path: &Path,
+    max_frame_dim: u32,
+    opts: &ConvertOptions,
+    mut on_frame: impl FnMut(usize, usize),
+) -> Result<Vec<BrailleFrame<'static>>, Box<dyn Error>> {
+    let sources = SourceFrames::open(path, max_frame_dim)?;
+    let total = sources.total();
+    let mut frames = Vec::new();
+    for source in sources {
+        let (image, delay) = source?;
+        frames.push(BrailleFrame { delay, ..convert_image(image, opts, None) });
+        on_frame(frames.len(), total);
+    }
+    Ok(frames)
+}
+
+/// Lazily decodes and converts one frame of a GIF (or APNG, animated WebP or still image)
+/// per `next()`, so only the compact cell lines of the frames pulled so far are kept.
+/// Frames wider or taller than `max_frame_dim` are rejected; failing to read or open the
+/// file is the first (and only) item.
+pub fn frames(
+    path: &Path,
+    max_frame_dim: u32,
+    opts: &ConvertOptions,
+) -> impl Iterator<Item = Result<BrailleFrame<'static>, Box<dyn Error>>> {
+    let opts = opts.clone();
+    let sources: Box<dyn Iterator<Item = _>> = match SourceFrames::open(path, max_frame_dim) {
+        Ok(sources) => Box::new(sources),
+        Err(err) => Box::new(std::iter::once(Err(err))),
+    };
+    sources.map(move |source| {
+        let (image, delay) = source?;
+        Ok(BrailleFrame { delay, ..convert_image(image, &opts, None) })
+    })
+}
+
+/// A composited source frame and how long it's shown.
+pub type SourceFrame = (RgbaImage, Duration);
+
+/// The source frames of one file (a still image as one frame), decoded one per `next()`.
+pub struct SourceFrames {
+    frames: Box<dyn Iterator<Item = Result<SourceFrame, Box<dyn Error>>>>,
+    total: usize,
+}
+
+impl SourceFrames {
+    /// Opens `path`, rejecting frames wider or taller than `max_frame_dim`.
+    pub fn open(path: &Path, max_frame_dim: u32) -> Result<Self, Box<dyn Error>> {
+        let bytes = fs::read(path)?;
+        if !is_animation(&bytes) {
+            let image = open_still(&bytes, max_frame_dim)?;
+            return Ok(SourceFrames { frames: Box::new(std::iter::once(Ok((image, DEFAULT_FRAME_DELAY)))), total: 1 });
+        }
+        // The compressed file is small; scanning it gives the GIF frame total without
+        // decoding anything. APNG / WebP frame counts aren't scanned for
+        let total = if bytes.starts_with(b"GIF") { scan_gif(&bytes).frames } else { 0 };
+        let frames = open_animation(bytes, max_frame_dim)?.map(|frame| {
+            let frame = frame?;
+            let delay = native_delay(frame.delay());
+            Ok((frame.into_buffer(), delay))
+        });
+        Ok(SourceFrames { frames: Box::new(frames), total })
+    }
+
+    /// How many frames the file says it has, or 0 when it doesn't say.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+impl Iterator for SourceFrames {
+    type Item = Result<SourceFrame, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout as AllocLayout, System};
+    use std::cell::Cell;
+
+    /// Wraps the system allocator and tracks bytes still allocated, per thread, so
+    /// tests running in parallel don't see each other's allocations.
+    struct CountingAlloc;
+
+    thread_local! {
+        static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+    }
+
+    fn track(delta: isize) {
+        let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+    }
+
+    fn live_bytes() -> isize {
+        LIVE_BYTES.with(Cell::get)
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: AllocLayout) -> *mut u8 {
+            track(layout.size() as isize);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: AllocLayout) {
+            track(-(layout.size() as isize));
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    /// Six 200x200 px frames of 100 ms each.
+    fn fixture_path() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/anim.webp")
+    }
+
+    /// A small frame with a moving gradient so dots and colors vary between frames.
+    fn synthetic_frame(i: u32) -> RgbaImage {
+        RgbaImage::from_fn(32, 32, |x, y| {
+            let v = ((x + y + i) * 8 % 256) as u8;
+            Rgba([v, 255 - v, v / 2, 255])
+        })
+    }
+
+    /// Splices an APP1 EXIF segment carrying `orientation` in right after the SOI marker.
+    fn with_exif_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        exif.extend_from_slice(&orientation.to_be_bytes());
+        exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(&exif);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn jpeg_exif_orientation_is_applied() {
+        // 32×16, white on the left half and black on the right
+        let source = image::RgbImage::from_fn(32, 16, |x, _| {
+            if x < 16 { image::Rgb([255; 3]) } else { image::Rgb([0; 3]) }
+        });
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 95)
+            .encode(&source, 32, 16, image::ColorType::Rgb8)
+            .unwrap();
+        let rotated = with_exif_orientation(&jpeg, 6);
+        assert_eq!(jpeg_orientation(&rotated), Some(6));
+        assert_eq!(jpeg_orientation(&jpeg), None);
+
+        // Orientation 6 means "rotate 90° clockwise to display": the left half ends up on top
+        let upright = open_still(&rotated, DEFAULT_MAX_FRAME_DIM).unwrap();
+        assert_eq!(upright.dimensions(), (16, 32));
+        assert!(upright.get_pixel(8, 4)[0] > 200, "top should be white");
+        assert!(upright.get_pixel(8, 28)[0] < 50, "bottom should be black");
+
+        let unrotated = open_still(&jpeg, DEFAULT_MAX_FRAME_DIM).unwrap();
+        assert_eq!(unrotated.dimensions(), (32, 16));
+    }
+
+    #[test]
+    fn convert_frame_defaults_match_plain_conversion() {
+        let image = synthetic_frame(7);
+        let expected = rgba_to_braille_colored(&image);
+        assert_eq!(convert_frame(&image, &Default::default()), expected);
+    }
+
+    #[test]
+    fn solid_white_cell_lights_every_dot() {
+        let white = RgbaImage::from_pixel(2, 4, Rgba([255, 255, 255, 255]));
+        let lines = rgba_to_braille_colored(&white);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].spans.len(), 1);
+        assert_eq!(lines[0].spans[0].content, "\u{28FF}");
+    }
+
+    #[test]
+    fn dots_use_the_unicode_layout() {
+        // Top right is dot 4 (U+2808), bottom left dot 7 (U+2840)
+        for ((x, y), glyph) in [((1, 0), "\u{2808}"), ((0, 3), "\u{2840}")] {
+            let mut img = RgbaImage::from_pixel(2, 4, Rgba([0, 0, 0, 255]));
+            img.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            assert_eq!(rgba_to_braille_colored(&img)[0].spans[0].content, glyph);
+        }
+    }
+
+    #[test]
+    fn transparent_image_is_blank() {
+        let clear = RgbaImage::from_pixel(6, 8, Rgba([255, 255, 255, 0]));
+        let lines = rgba_to_braille_colored(&clear);
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().flat_map(|line| &line.spans).all(|span| span.content == "\u{2800}"));
+    }
+
+    #[test]
+    fn convert_gif_fits_the_requested_cells() {
+        let frames = convert_gif(&fixture_path(), 20, 5).unwrap();
+        assert_eq!(frames.len(), 6);
+        // 200x200 px fits 20x5 cells (40x20 px) as 20x20 px, i.e. 10x5 cells
+        assert!(frames.iter().all(|frame| frame.lines.len() == 5 && frame.lines[0].spans.len() == 10));
+        assert_eq!(frames[0].delay, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn frames_are_converted_on_demand() {
+        let path = fixture_path();
+        let opts = ConvertOptions { max_cells: Some((20, 5)), ..ConvertOptions::default() };
+        let mut pulled = frames(&path, DEFAULT_MAX_FRAME_DIM, &opts);
+        let first = pulled.next().unwrap().unwrap();
+        assert_eq!((first.lines.len(), first.delay), (5, Duration::from_millis(100)));
+        assert_eq!(pulled.count(), 5);
+
+        let mut missing = frames(Path::new("no/such/file.gif"), DEFAULT_MAX_FRAME_DIM, &opts);
+        assert!(missing.next().unwrap().is_err());
+        assert!(missing.next().is_none());
+
+        // The 200x200 px fixture is over a 100 px limit
+        assert!(frames(&path, 100, &opts).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn progress_counts_up_without_a_known_total() {
+        let path = fixture_path();
+        let mut calls = Vec::new();
+        let frames = convert_gif_with_progress(&path, DEFAULT_MAX_FRAME_DIM, &ConvertOptions::default(), |done, total| {
+            calls.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(frames.len(), 6);
+        // WebP doesn't say how many frames it has up front
+        assert_eq!(calls, (1..=6).map(|done| (done, 0)).collect::<Vec<_>>());
+    }
+
+    /// A 3-frame GIF, with a Netscape loop block when `repeat` is given.
+    fn encoded_gif(repeat: Option<image::codecs::gif::Repeat>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut bytes);
+        if let Some(repeat) = repeat {
+            encoder.set_repeat(repeat).unwrap();
+        }
+        for i in 0..3 {
+            encoder.encode_frame(image::Frame::new(synthetic_frame(i))).unwrap();
+        }
+        drop(encoder);
+        bytes
+    }
+
+    #[test]
+    fn scan_gif_reads_frames_and_loop_count() {
+        use image::codecs::gif::Repeat;
+        let finite = scan_gif(&encoded_gif(Some(Repeat::Finite(3))));
+        assert_eq!((finite.frames, finite.loop_count, finite.plays()), (3, Some(3), Some(4)));
+        let forever = scan_gif(&encoded_gif(Some(Repeat::Infinite)));
+        assert_eq!((forever.loop_count, forever.plays()), (Some(0), None));
+        assert_eq!(scan_gif(&encoded_gif(None)).plays(), Some(1));
+    }
+
+    #[test]
+    fn scan_gif_stops_at_truncation() {
+        let bytes = encoded_gif(Some(image::codecs::gif::Repeat::Finite(3)));
+        // Every prefix scans without panicking and never finds more than the whole file has
+        for len in 0..bytes.len() {
+            assert!(scan_gif(&bytes[..len]).frames <= 3);
+        }
+        // A frame counts once its descriptor is read, even if its pixel data is cut off
+        assert_eq!(scan_gif(&bytes[..bytes.len() - 4]).frames, 3);
+        assert_eq!(scan_gif(b"GIF89a").frames, 0);
+    }
+
+    #[test]
+    fn ansi16_picks_the_nearest_standard_color() {
+        assert_eq!(nearest_ansi16(10, 10, 10), Color::Black);
+        assert_eq!(nearest_ansi16(250, 20, 30), Color::LightRed);
+        assert_eq!(nearest_ansi16(120, 130, 125), Color::DarkGray);
+        assert_eq!(ColorMode::Ansi16.apply(Color::Rgb(0, 190, 200)), Some(Color::Cyan));
+    }
+
+    #[test]
+    fn thin_sources_report_the_clamped_side() {
+        // A normal 80x24 pane is 160x96 px; 1000x1 shrinks to 160x0.16, clamped to 1 px tall
+        assert_eq!(compute_scaled_dims_checked(1000, 1, 160, 96), ((160, 1), true));
+        assert_eq!(compute_scaled_dims(1000, 1, 160, 96), (160, 1));
+        assert_eq!(compute_scaled_dims_checked(400, 100, 160, 96), ((160, 40), false));
+        assert_eq!(compute_scaled_dims_checked(1, 1000, 160, 96), ((1, 96), true));
+    }
+
+    #[test]
+    fn braille_table_covers_every_dot_pattern() {
+        assert_eq!(BRAILLE_GLYPHS.chars().count(), 256);
+        assert_eq!(braille_str(0), "\u{2800}");
+        assert_eq!(braille_str(0b1101_0010), "\u{28D2}");
+        assert_eq!(braille_str(0xFF), "\u{28FF}");
+    }
+
+    #[test]
+    fn converting_many_frames_does_not_leak() {
+        // Warm up once so lazily initialized state doesn't count as growth
+        drop(rgba_to_braille_colored(&synthetic_frame(0)));
+
+        let before = live_bytes();
+        for i in 0..1000 {
+            let frame = synthetic_frame(i);
+            drop(rgba_to_braille_colored(&frame));
+        }
+        assert_eq!(live_bytes() - before, 0, "conversion left memory allocated");
+    }
+}
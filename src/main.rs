@@ -1,272 +1,2730 @@
 use std::{
+    collections::BTreeMap,
     error::Error,
-    fs::File,
-    io,
-    path::PathBuf,
+    fs::{self, File},
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    str::FromStr,
     time::{Duration, Instant},
 };
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor,
+    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
-    Terminal,
+    Frame, Terminal, TerminalOptions, Viewport,
 };
 
-use image::codecs::gif::GifDecoder;
-use image::{imageops, AnimationDecoder, ImageBuffer, Rgba, RgbaImage};
+use image::{imageops, Rgba, RgbaImage};
 
-/// Holds the braille + color lines for a single frame (no per‐frame delay).
-struct BrailleFrame<'a> {
-    lines: Vec<Line<'a>>,
+use gif_braille_tui::{
+    braille_glyph, color_to_rgb, compute_scaled_dims_checked, convert_image, is_animation, mirror_glyph,
+    recolor_frames, scan_gif, srgb_to_linear, BrailleFrame, ColorMode, ColorSum, ConvertOptions, SourceFrame, SourceFrames,
+    ANSI16, BRAILLE_DOT_BITS, DEFAULT_MAX_FRAME_DIM, QUADRANT_GLYPHS,
+};
+
+/// Share of cells (in percent) `--focus-peak` highlights unless `--focus-peak-top` says otherwise.
+const DEFAULT_FOCUS_PEAK_TOP: f32 = 10.0;
+
+const USAGE: &str = "\
+Usage: gif_braille_tui [options] <path_to_gif>
+
+GIFs, APNGs and animated WebPs play with their own frame timing; other
+images (PNG, JPEG, ...) are shown as a single frame.
+
+Options:
+  --max-frame-dim <px>    reject frames wider or taller than this (default 4096)
+  --compare <other.gif>   play a second GIF side by side
+  --sprite-sheet <file>   render every frame into one PNG grid and exit
+  --cols <n>              frames per row in the sprite sheet
+  --preserve-edges        keep thin lines that fall below the brightness threshold
+  --extract-frames <range>
+                          write the selected frames (e.g. 0-4,9) as PNGs and exit
+  --out <dir>             output directory for --extract-frames
+  --tone-map <op>         reinhard, aces or none (default) before converting
+  --every <n>             keep only every nth frame
+  --motion-blur           with --every, blend skipped frames into the kept ones
+  --two-tone              color lit dots and the cell background separately
+  --export-colorspace <cs>
+                          srgb (default) or linear colors in exported files
+  --keep-hue <degrees>    keep only cells near this hue in color, gray the rest
+  --hue-range <degrees>   tolerance around --keep-hue (default 30)
+  --dot-mask <hex>        only ever light these dot bits (e.g. c9 for the corners)
+  --glyph <binary>        print the braille glyph for these dot bits and exit
+  --mode <mode>           braille (default), blocks for a plain color mosaic, or
+                          quadrant for 2x2 blocks with two colors per cell
+  --bpm <n>               play one full loop per beat at this tempo (1 to 1000)
+  --beats <n>             with --bpm, beats per loop (default 1)
+  --title-format <fmt>    title template using {name} {frame} {total} {time}
+                          {duration} {fps}
+  --term-size <colsxrows> convert for this terminal size instead of the current one
+  --ease <curve>          in, out or in-out speed ramp over each loop
+  --detect-loop           skip the intro and play only the seamless looping part
+  --cast <file.cast>      write an asciinema v2 recording and exit
+  --checksums             print a stable hash of each converted frame and exit
+  --palette <hex,...>     map colors onto a custom palette (e.g. #1d2021,#fabd2f)
+  --palette-blend         blend the two nearest palette colors instead of snapping
+  --frame-delay <n:ms>    show frame n for ms milliseconds (repeatable)
+  --loop-count <n>        play n times (0 = forever) instead of the GIF's own count
+  --on-finish <action>    hold (default) the last frame or exit after the last loop
+  --min-loop-frames <n>   loops with fewer frames show each one for at least 200ms
+                          (default 4; off with --bpm)
+  --static-color <src>    freeze cell colors from the first frame or the average of
+                          all frames (first|average); only the dots animate
+  --prescale-blur <sigma> blur before downscaling, scaled by the shrink factor
+  --save-cache <file>     also save the converted frames for fast reloading
+  --load-cache <file>     play frames saved with --save-cache instead of converting
+  --watermark <image>     composite this logo onto every frame before converting
+  --watermark-pos <corner>
+                          top-left, top-right, bottom-left or bottom-right (default)
+  --watermark-opacity <a> logo opacity from 0 to 1 (default 0.6)
+  --focus-peak            highlight the cells with the most edge detail in red
+  --focus-peak-top <pct>  with --focus-peak, how many cells to highlight (default 10)
+  --checker               show fully transparent cells as a dim checkerboard
+  --dot-scale <f>         sample dots at f times the cell grid's resolution (default 1)
+  --color-scale <f>       sample colors at f times the cell grid's resolution (default 1)
+  --scroll-v <rows/s>     fit the width only and scroll the tall result like credits
+  --scroll-end <end>      stop (default) at the bottom, or loop back to the top
+  --trim-duplicates       drop repeated identical frames at the start and end
+  --portrait-fill <mode>  fill the space beside narrow content: center (with a faded
+                          edge gradient), tile or mirror
+  --cell-color <method>   average (default), center-weighted, dominant or lit
+  --crossfade <ms>        dissolve colors into each new frame over this many ms
+  --tile <colsxrows>      cut the picture into a grid and show one tile at full detail
+  --tile-index <n>        which tile to start on, in reading order (default 0)
+  --hold-on-unique <f>    hold frames that change the picture a lot up to 1+f times
+                          longer (f up to 100)
+  --rtl                   lay out cells and titles for a right-to-left terminal
+  --colors <mode>         truecolor (default), 256, 16 for the standard ANSI colors,
+                          gray or mono
+  --crt                   dim every other row like CRT scanlines
+  --crt-intensity <0-1>   how much --crt dims the scanlines (default 0.3)
+  --crt-band              with --crt, roll a brighter band down once per loop
+  --thin-frames <mode>    clamp (default) frames too thin for the pane to 1 px with a
+                          warning, or skip them
+  --inline-cursor         play in the normal screen below the prompt, redrawing in
+                          place, instead of taking over the whole terminal
+  --inline-rows <n>       with --inline-cursor, rows of cells to play in (default 12)
+  --pause-at <frames>     pause on reaching these frames (e.g. 0,12,30-32) until
+                          space is pressed
+
+Keys:
+  q                       quit
+  c                       cycle colors: truecolor, 256, 16, gray, mono
+  f                       toggle the --focus-peak overlay
+  [ ]                     previous / next --tile
+  space                   pause / resume
+  left right              while paused, step back / forward one frame
+  + -                     play faster / slower";
+
+/// Command-line options.
+struct Options {
+    gif_path: PathBuf,
+    /// Largest width or height (in pixels) a GIF frame may declare.
+    max_frame_dim: u32,
+    /// Second GIF to play next to the first one.
+    compare_path: Option<PathBuf>,
+    /// Write all frames into one PNG instead of playing them.
+    sprite_sheet: Option<PathBuf>,
+    /// Frames per row in the sprite sheet (defaults to a roughly square grid).
+    sheet_cols: Option<u32>,
+    /// Frames to dump as PNGs (no TUI), together with `out_dir`.
+    extract_frames: Option<FrameRange>,
+    /// Destination directory for `--extract-frames`.
+    out_dir: Option<PathBuf>,
+    /// Print the braille glyph for these dot bits instead of playing anything.
+    glyph: Option<u8>,
+    /// Keep only every Nth frame.
+    every: usize,
+    /// With `every`, average each group of frames into the kept one instead of dropping them.
+    motion_blur: bool,
+    /// Color encoding for exported files.
+    export_colorspace: ExportColorspace,
+    /// Lock the loop length to a musical tempo: one loop per `beats` beats.
+    bpm: Option<f64>,
+    beats: u32,
+    /// Window title with `{name}`, `{frame}`, `{total}`, `{time}`, `{duration}`, `{fps}`.
+    title_format: Option<String>,
+    /// Convert for this many terminal columns × rows instead of asking the terminal.
+    term_size: Option<(u16, u16)>,
+    ease: Ease,
+    /// Drop any intro and play only the most seamlessly looping stretch of frames.
+    detect_loop: bool,
+    /// Write an asciinema cast instead of playing.
+    cast: Option<PathBuf>,
+    /// Print a hash per converted frame instead of playing.
+    checksums: bool,
+    /// Per-frame delay overrides from repeated `--frame-delay <frame>:<ms>`.
+    frame_delays: BTreeMap<usize, Duration>,
+    /// Number of times to play, overriding the GIF's own loop count (0 = forever).
+    loop_count: Option<u32>,
+    on_finish: OnFinish,
+    /// Loops with fewer frames than this keep each frame up for at least
+    /// `SHORT_LOOP_MIN_DELAY`.
+    min_loop_frames: usize,
+    /// Keep cell colors fixed across frames so only the dots animate.
+    static_color: Option<StaticColor>,
+    save_cache: Option<PathBuf>,
+    load_cache: Option<PathBuf>,
+    watermark: Option<PathBuf>,
+    watermark_pos: Corner,
+    watermark_opacity: f32,
+    /// Percentage of the most detailed cells to highlight, if focus peaking is on.
+    focus_peak: Option<f32>,
+    /// Rows per second to scroll a tall rendering by, like end credits.
+    scroll_v: Option<f64>,
+    scroll_end: ScrollEnd,
+    trim_duplicates: bool,
+    portrait_fill: Option<PortraitFill>,
+    /// Dissolve each frame's colors into the next over this long.
+    crossfade: Option<Duration>,
+    /// Show only one tile of the picture cut into this many columns × rows.
+    tile: Option<(u32, u32)>,
+    tile_index: usize,
+    /// Hold frames that change a lot for up to `1 + factor` times their delay.
+    hold_on_unique: Option<f64>,
+    /// Dim every other row by this share of its brightness (`--crt`).
+    crt: Option<f32>,
+    /// With `crt`, also roll a brighter band down the picture once per loop.
+    crt_band: bool,
+    /// Frames playback pauses on when it reaches them.
+    pause_at: Option<FrameRange>,
+    /// Play in a few lines of the normal buffer instead of on the alternate screen.
+    inline_cursor: bool,
+    /// Most rows of cells `--inline-cursor` converts for, leaving the prompt in view.
+    inline_rows: u32,
+    thin_frames: ThinFrames,
+    /// `--colors`: exports are converted in this mode; playback starts in it, recoloring the
+    /// truecolor frames so `c` can still cycle back.
+    colors: ColorMode,
+    /// How each frame is turned into cells.
+    convert: ConvertOptions,
+}
+
+/// Corner of the frame a `--watermark` is placed in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl FromStr for Corner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            _ => Err(format!("unknown corner: {s}")),
+        }
+    }
+}
+
+/// What `--portrait-fill` puts in the empty space beside content narrower than the pane.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PortraitFill {
+    Center,
+    Tile,
+    Mirror,
+}
+
+impl FromStr for PortraitFill {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "center" => Ok(PortraitFill::Center),
+            "tile" => Ok(PortraitFill::Tile),
+            "mirror" => Ok(PortraitFill::Mirror),
+            _ => Err(format!("unknown --portrait-fill mode: {s}")),
+        }
+    }
+}
+
+/// What to do with frames so thin that fitting them into the pane clamps a side to 1 px.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ThinFrames {
+    /// Show them stretched to 1 px, with a warning.
+    Clamp,
+    /// Leave them out.
+    Skip,
+}
+
+impl FromStr for ThinFrames {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(ThinFrames::Clamp),
+            "skip" => Ok(ThinFrames::Skip),
+            _ => Err(format!("unknown --thin-frames mode: {s}")),
+        }
+    }
+}
+
+/// Where `--static-color` takes its fixed cell colors from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StaticColor {
+    First,
+    Average,
+}
+
+impl FromStr for StaticColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(StaticColor::First),
+            "average" => Ok(StaticColor::Average),
+            _ => Err(format!("unknown --static-color source: {s}")),
+        }
+    }
+}
+
+/// What to do once the animation has played as many times as it should.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OnFinish {
+    /// Keep showing the last frame until the user quits.
+    Hold,
+    Exit,
+}
+
+impl FromStr for OnFinish {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hold" => Ok(OnFinish::Hold),
+            "exit" => Ok(OnFinish::Exit),
+            _ => Err(format!("unknown --on-finish action: {s}")),
+        }
+    }
+}
+
+/// Speed ramp applied across each loop of the animation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Ease {
+    None,
+    /// Start slow, end fast.
+    In,
+    /// Start fast, end slow.
+    Out,
+    /// Slow at both ends, fast in the middle.
+    InOut,
+}
+
+impl Ease {
+    /// Wall-clock fraction of the loop at which `progress` (0..=1) of the frames have played.
+    fn time_at(self, progress: f64) -> f64 {
+        match self {
+            Ease::None => progress,
+            Ease::In => progress.sqrt(),
+            Ease::Out => 1.0 - (1.0 - progress).sqrt(),
+            Ease::InOut if progress < 0.5 => (progress / 2.0).sqrt(),
+            Ease::InOut => 1.0 - ((1.0 - progress) / 2.0).sqrt(),
+        }
+    }
+}
+
+impl FromStr for Ease {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Ease::None),
+            "in" => Ok(Ease::In),
+            "out" => Ok(Ease::Out),
+            "in-out" => Ok(Ease::InOut),
+            _ => Err(format!("unknown easing: {s}")),
+        }
+    }
+}
+
+/// How colors are encoded in exported files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExportColorspace {
+    /// Gamma-encoded, exactly as shown on screen.
+    Srgb,
+    /// Linear-light values scaled to 0..=255.
+    Linear,
+}
+
+impl FromStr for ExportColorspace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "srgb" => Ok(ExportColorspace::Srgb),
+            "linear" => Ok(ExportColorspace::Linear),
+            _ => Err(format!("unknown color space: {s}")),
+        }
+    }
+}
+
+/// Inclusive frame index ranges such as `3`, `2-8`, `10-` or `0-4,9`.
+struct FrameRange(Vec<(usize, Option<usize>)>);
+
+impl FrameRange {
+    fn contains(&self, index: usize) -> bool {
+        self.0
+            .iter()
+            .any(|&(start, end)| index >= start && end.is_none_or(|end| index <= end))
+    }
+
+    /// The highest index the range can contain, or `None` when part of it is open-ended.
+    fn last(&self) -> Option<usize> {
+        self.0.iter().try_fold(0, |last, &(_, end)| end.map(|end| last.max(end)))
+    }
+}
+
+impl FromStr for FrameRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_index = |part: &str| part.trim().parse::<usize>().map_err(|_| format!("bad frame index: {part}"));
+        let mut ranges = Vec::new();
+        for part in s.split(',') {
+            let range = match part.split_once('-') {
+                Some((start, end)) => {
+                    let start = if start.trim().is_empty() { 0 } else { parse_index(start)? };
+                    let end = if end.trim().is_empty() { None } else { Some(parse_index(end)?) };
+                    if end.is_some_and(|end| end < start) {
+                        return Err(format!("empty frame range: {part}"));
+                    }
+                    (start, end)
+                }
+                None => {
+                    let index = parse_index(part)?;
+                    (index, Some(index))
+                }
+            };
+            ranges.push(range);
+        }
+        Ok(FrameRange(ranges))
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            gif_path: PathBuf::new(),
+            max_frame_dim: DEFAULT_MAX_FRAME_DIM,
+            compare_path: None,
+            sprite_sheet: None,
+            sheet_cols: None,
+            extract_frames: None,
+            out_dir: None,
+            glyph: None,
+            every: 1,
+            motion_blur: false,
+            export_colorspace: ExportColorspace::Srgb,
+            bpm: None,
+            beats: 1,
+            title_format: None,
+            term_size: None,
+            ease: Ease::None,
+            detect_loop: false,
+            cast: None,
+            checksums: false,
+            frame_delays: BTreeMap::new(),
+            loop_count: None,
+            on_finish: OnFinish::Hold,
+            min_loop_frames: 4,
+            static_color: None,
+            save_cache: None,
+            load_cache: None,
+            watermark: None,
+            watermark_pos: Corner::BottomRight,
+            watermark_opacity: 0.6,
+            focus_peak: None,
+            scroll_v: None,
+            scroll_end: ScrollEnd::Stop,
+            trim_duplicates: false,
+            portrait_fill: None,
+            crossfade: None,
+            tile: None,
+            tile_index: 0,
+            hold_on_unique: None,
+            crt: None,
+            crt_band: false,
+            pause_at: None,
+            inline_cursor: false,
+            inline_rows: DEFAULT_INLINE_ROWS,
+            thin_frames: ThinFrames::Clamp,
+            colors: ColorMode::Truecolor,
+            convert: ConvertOptions::default(),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // 1) Parse CLI arguments
+    let mut opts = match parse_args(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(dots) = opts.glyph {
+        println!("{}", braille_glyph(dots));
+        return Ok(());
+    }
+
+    let exporting = opts.extract_frames.is_some() || opts.sprite_sheet.is_some() || opts.cast.is_some() || opts.checksums;
+    if exporting {
+        opts.convert.color_mode = opts.colors;
+    }
+
+    if let (Some(range), Some(out_dir)) = (&opts.extract_frames, &opts.out_dir) {
+        extract_frames(&opts.gif_path, range, out_dir, &opts)?;
+        return Ok(());
+    }
+
+    // 2) Decode + convert all frames into braille/color lines (or reload them from a cache)
+    let mut frames = match &opts.load_cache {
+        Some(cache_path) => {
+            let frames = read_cache(cache_path, pane_cells(&opts))?;
+            if opts.focus_peak.is_some() && frames.iter().all(|frame| frame.peaks.is_empty()) {
+                eprintln!("Warning: {} was saved without --focus-peak, so there are no cells to highlight", cache_path.display());
+            }
+            frames
+        }
+        None => {
+            let (frames, thin) = load_and_convert_gif(&opts.gif_path, &opts)?;
+            warn_thin(&opts.gif_path, thin, opts.thin_frames);
+            frames
+        }
+    };
+    if frames.is_empty() {
+        eprintln!("No frames found or failed to decode GIF.");
+        std::process::exit(1);
+    }
+    if let Some(cache_path) = &opts.save_cache {
+        write_cache(&frames, pane_cells(&opts), cache_path)?;
+    }
+
+    if let Err(msg) = apply_frame_delays(&mut frames, &opts) {
+        eprintln!("{msg}");
+        std::process::exit(1);
+    }
+
+    if let Some(sheet_path) = &opts.sprite_sheet {
+        write_sprite_sheet(&frames, opts.sheet_cols, opts.export_colorspace, sheet_path)?;
+        return Ok(());
+    }
+    if let Some(cast_path) = &opts.cast {
+        write_cast(&frames, &opts, cast_path)?;
+        return Ok(());
+    }
+    if opts.checksums {
+        let mut out = io::stdout().lock();
+        for (i, frame) in frames.iter().enumerate() {
+            writeln!(out, "{i}\t{:016x}", frame_checksum(frame))?;
+        }
+        return Ok(());
+    }
+
+    let compare_frames = match &opts.compare_path {
+        Some(path) => {
+            let (frames, thin) = load_and_convert_gif(path, &opts)?;
+            warn_thin(path, thin, opts.thin_frames);
+            if frames.is_empty() {
+                eprintln!("No frames found or failed to decode {}.", path.display());
+                std::process::exit(1);
+            }
+            Some(frames)
+        }
+        None => None,
+    };
+    let mut compare_frames = compare_frames;
+
+    if let Some(template) = &opts.title_format {
+        for key in unknown_placeholders(template) {
+            eprintln!("Warning: unknown title placeholder {{{key}}} will be shown as-is");
+        }
+    }
+
+    // Honor the GIF's Netscape loop count like a browser would, unless overridden
+    let plays = match opts.loop_count {
+        Some(0) => None,
+        Some(n) => Some(n),
+        None => source_plays(&fs::read(&opts.gif_path)?),
+    };
+
+    // 3) Set up terminal; the guard (and the panic hook, which runs before the panic
+    // message is printed) put it back even if something panics mid-playback
+    let inline = opts.inline_cursor;
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal(inline);
+        default_hook(info);
+    }));
+    let guard = TerminalGuard::enter(inline)?;
+    let mut viewport_height = inline_height(&frames, compare_frames.as_deref());
+    let viewport = if inline { Viewport::Inline(viewport_height) } else { Viewport::Fullscreen };
+    let mut terminal = Terminal::with_options(CrosstermBackend::new(io::stdout()), TerminalOptions { viewport })?;
+    // Start ratatui's diffing from the blank screen it is actually looking at
+    terminal.clear()?;
+
+    // 4) Run the TUI loop to display frames at ~60 fps; moving to another `--tile` converts
+    // that tile and starts it playing, resizing the terminal converts again for the new size
+    let mut playback = Playback::new(&opts);
+    // Decoded source frames (main, compare), loaded on the first resize and kept so later
+    // ones only have to rescale
+    let mut sources: Option<(Vec<SourceFrame>, Option<Vec<SourceFrame>>)> = None;
+    let res: Result<(), Box<dyn Error>> = loop {
+        let compare = opts
+            .compare_path
+            .as_deref()
+            .zip(compare_frames.as_deref())
+            .map(|(path, frames)| (file_label(path), frames));
+        match run_app(&mut terminal, (file_label(&opts.gif_path), &frames), compare, plays, playback, &opts) {
+            Ok(AppExit::Quit) => break Ok(()),
+            Ok(AppExit::Resize(state)) => {
+                playback = state;
+                // Cached frames only exist for the pane size they were saved at
+                if opts.load_cache.is_some() {
+                    continue;
+                }
+                if sources.is_none() {
+                    let loaded = load_sources(&opts.gif_path, &opts).and_then(|main| {
+                        let compare = match &opts.compare_path {
+                            Some(path) => Some(load_sources(path, &opts)?),
+                            None => None,
+                        };
+                        Ok((main, compare))
+                    });
+                    match loaded {
+                        Ok((main, compare)) if !main.is_empty() => sources = Some((main, compare)),
+                        Ok(_) => continue,
+                        Err(err) => break Err(err),
+                    }
+                }
+                // Keep the old frames if nothing is left at the new size (`--thin-frames skip`).
+                // The frame count was only checked against `--frame-delay` at startup; see
+                // `apply_frame_delays` for what happens to overrides past a new, shorter end
+                let converted = sources.as_ref().map(|(main, _)| convert_sources(main, &opts));
+                if let (Some(main), Some((_, compare))) = (converted.filter(|frames| !frames.is_empty()), &sources) {
+                    frames = main;
+                    let _ = apply_frame_delays(&mut frames, &opts);
+                    compare_frames = compare.as_ref().map(|sources| convert_sources(sources, &opts)).filter(|frames| !frames.is_empty());
+                }
+            }
+            Ok(AppExit::Tile(index, state)) => {
+                opts.tile_index = index;
+                playback = Playback { frame: 0, compare_frame: 0, ..state };
+                // The kept sources are cut to the old tile
+                sources = None;
+                // These run on the alternate screen, so thin frames and `--frame-delay`
+                // overrides past the new tile's end (see `apply_frame_delays`) go unreported
+                let reloaded = load_and_convert_gif(&opts.gif_path, &opts).and_then(|(mut main, _)| {
+                    let _ = apply_frame_delays(&mut main, &opts);
+                    let compare = match &opts.compare_path {
+                        Some(path) => Some(load_and_convert_gif(path, &opts)?.0),
+                        None => None,
+                    };
+                    Ok((main, compare))
+                });
+                match reloaded {
+                    Ok((main, compare)) if !main.is_empty() => {
+                        frames = main;
+                        compare_frames = compare.filter(|frames| !frames.is_empty());
+                    }
+                    Ok(_) => {}
+                    Err(err) => break Err(err),
+                }
+            }
+            Err(err) => break Err(err.into()),
+        }
+
+        // Inline, grow or shrink the viewport to the reconverted frames: clear the old one
+        // and open the new one from its top line
+        let height = inline_height(&frames, compare_frames.as_deref());
+        if inline && height != viewport_height {
+            viewport_height = height;
+            let top = terminal.get_frame().area().top();
+            if let Err(err) = execute!(io::stdout(), cursor::MoveTo(0, top), terminal::Clear(terminal::ClearType::FromCursorDown)) {
+                break Err(err.into());
+            }
+            let viewport = Viewport::Inline(viewport_height);
+            terminal = match Terminal::with_options(CrosstermBackend::new(io::stdout()), TerminalOptions { viewport }) {
+                Ok(terminal) => terminal,
+                Err(err) => break Err(err.into()),
+            };
+        }
+    };
+
+    // 5) Restore terminal; inline, the last frame stays on screen with the cursor on the
+    // line below it
+    let last_line = terminal.get_frame().area().bottom().saturating_sub(1);
+    drop(guard);
+    if inline {
+        execute!(io::stdout(), cursor::MoveTo(0, last_line))?;
+        println!();
+    }
+
+    if let Err(err) = res {
+        eprintln!("Error: {err:?}");
+    }
+    Ok(())
+}
+
+/// Lines `--inline-cursor` keeps under the cursor: the tallest frame and its border.
+/// ratatui moves the cursor back up to redraw them in place.
+fn inline_height(frames: &[BrailleFrame<'static>], compare: Option<&[BrailleFrame<'static>]>) -> u16 {
+    let tallest = frames.iter().chain(compare.into_iter().flatten()).map(|frame| frame.lines.len()).max();
+    (tallest.unwrap_or(0) + 2).min(u16::MAX as usize) as u16
+}
+
+/// Applies `--frame-delay` overrides. Frame indices count the frames as played, i.e.
+/// after --every / --detect-loop. Overrides are applied in index order, so when some are
+/// past the end every one before them still applies and the error names the first.
+/// Reconverting mid-playback (another size or tile) can change the frame count (e.g.
+/// `--detect-loop`, `--trim-duplicates`, `--thin-frames skip`); the player then ignores
+/// the error, dropping those overrides and keeping the rest on the same indices.
+fn apply_frame_delays(frames: &mut [BrailleFrame<'static>], opts: &Options) -> Result<(), String> {
+    for (&index, &delay) in &opts.frame_delays {
+        match frames.get_mut(index) {
+            Some(frame) => frame.delay = delay,
+            None => return Err(format!("--frame-delay {index}: the GIF only has {} frames", frames.len())),
+        }
+    }
+    Ok(())
+}
+
+/// Why `run_app` returned.
+enum AppExit {
+    Quit,
+    /// The user moved to another `--tile`; it has to be converted before playing on.
+    Tile(usize, Playback),
+    /// The pane changed size; the frames have to be converted for it, then playback
+    /// picks up where it was.
+    Resize(Playback),
+}
+
+/// Where playback is and how it's set up, carried across reconversions.
+#[derive(Clone, Copy)]
+struct Playback {
+    frame: usize,
+    compare_frame: usize,
+    paused: bool,
+    /// Playback speed multiplier, changed with `+` / `-`.
+    speed: f64,
+    color_mode: ColorMode,
+    peaking: bool,
+    /// Full loops played so far, counted against the loop count.
+    loops_done: u32,
+    /// Every loop has played and the last frame is being held.
+    finished: bool,
+}
+
+impl Playback {
+    fn new(opts: &Options) -> Self {
+        Playback {
+            frame: 0,
+            compare_frame: 0,
+            // `--pause-at 0` holds the very first frame too
+            paused: opts.pause_at.as_ref().is_some_and(|frames| frames.contains(0)),
+            speed: 1.0,
+            color_mode: opts.colors,
+            // `--focus-peak` starts with the overlay shown; `f` toggles it
+            peaking: opts.focus_peak.is_some(),
+            loops_done: 0,
+            finished: false,
+        }
+    }
+}
+
+/// Raw mode + alternate screen (or just raw mode, inline) for the lifetime of the value;
+/// dropping it (including while unwinding from a panic) restores the terminal.
+struct TerminalGuard {
+    inline: bool,
+}
+
+impl TerminalGuard {
+    /// With `inline` (`--inline-cursor`) the normal buffer is kept and only the cursor
+    /// is hidden. Bracketed paste is on either way, so pasted text arrives as one event
+    /// instead of a burst of key presses.
+    fn enter(inline: bool) -> io::Result<Self> {
+        enable_raw_mode()?;
+        // Construct before entering the alternate screen so a failure there still restores
+        let guard = TerminalGuard { inline };
+        if inline {
+            execute!(io::stdout(), cursor::Hide, EnableBracketedPaste)?;
+            return Ok(guard);
+        }
+        // Blank the alternate screen and hide the cursor straight away, so whatever the
+        // terminal had there never shows before the first frame is drawn
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::Hide,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(self.inline);
+    }
+}
+
+/// Leaves raw mode and (unless `inline`) the alternate screen and shows the cursor. Safe
+/// to call twice.
+fn restore_terminal(inline: bool) {
+    let _ = disable_raw_mode();
+    if inline {
+        let _ = execute!(io::stdout(), DisableBracketedPaste, cursor::Show);
+    } else {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, cursor::Show);
+    }
+}
+
+/// Parses the command line (everything after the program name) into `Options`.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut opts = Options::default();
+    let mut gif_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-frame-dim" => {
+                opts.max_frame_dim = match flag_value(&mut args, &arg)? {
+                    0 => return Err("--max-frame-dim must be positive".into()),
+                    dim => dim,
+                };
+            }
+            "--compare" => opts.compare_path = Some(flag_value(&mut args, &arg)?),
+            "--sprite-sheet" => opts.sprite_sheet = Some(flag_value(&mut args, &arg)?),
+            "--cols" => {
+                opts.sheet_cols = match flag_value(&mut args, &arg)? {
+                    0 => return Err("--cols must be positive".into()),
+                    cols => Some(cols),
+                };
+            }
+            "--preserve-edges" => opts.convert.preserve_edges = true,
+            "--extract-frames" => opts.extract_frames = Some(flag_value(&mut args, &arg)?),
+            "--tone-map" => opts.convert.tone_map = flag_value(&mut args, &arg)?,
+            "--every" => {
+                opts.every = match flag_value(&mut args, &arg)? {
+                    0 => return Err("--every must be positive".into()),
+                    n => n,
+                };
+            }
+            "--motion-blur" => opts.motion_blur = true,
+            "--two-tone" => opts.convert.two_tone = true,
+            "--export-colorspace" => opts.export_colorspace = flag_value(&mut args, &arg)?,
+            "--keep-hue" => opts.convert.keep_hue = Some(flag_value::<f32>(&mut args, &arg)?.rem_euclid(360.0)),
+            "--hue-range" => opts.convert.hue_range = flag_value(&mut args, &arg)?,
+            "--bpm" => {
+                opts.bpm = match flag_value(&mut args, &arg)? {
+                    bpm if (MIN_BPM..=MAX_BPM).contains(&bpm) => Some(bpm),
+                    _ => return Err(format!("--bpm must be between {MIN_BPM} and {MAX_BPM}")),
+                };
+            }
+            "--beats" => {
+                opts.beats = match flag_value(&mut args, &arg)? {
+                    0 => return Err("--beats must be positive".into()),
+                    beats => beats,
+                };
+            }
+            "--mode" => opts.convert.mode = flag_value(&mut args, &arg)?,
+            "--title-format" => opts.title_format = Some(flag_value(&mut args, &arg)?),
+            "--ease" => opts.ease = flag_value(&mut args, &arg)?,
+            "--detect-loop" => opts.detect_loop = true,
+            "--cast" => opts.cast = Some(flag_value(&mut args, &arg)?),
+            "--checksums" => opts.checksums = true,
+            "--palette" => opts.convert.palette = Some(flag_value(&mut args, &arg)?),
+            "--palette-blend" => opts.convert.palette_blend = true,
+            "--loop-count" => opts.loop_count = Some(flag_value(&mut args, &arg)?),
+            "--on-finish" => opts.on_finish = flag_value(&mut args, &arg)?,
+            "--min-loop-frames" => opts.min_loop_frames = flag_value(&mut args, &arg)?,
+            "--static-color" => opts.static_color = Some(flag_value(&mut args, &arg)?),
+            "--prescale-blur" => opts.convert.prescale_blur = Some(flag_value(&mut args, &arg)?),
+            "--save-cache" => opts.save_cache = Some(flag_value(&mut args, &arg)?),
+            "--load-cache" => opts.load_cache = Some(flag_value(&mut args, &arg)?),
+            "--watermark" => opts.watermark = Some(flag_value(&mut args, &arg)?),
+            "--watermark-pos" => opts.watermark_pos = flag_value(&mut args, &arg)?,
+            "--watermark-opacity" => opts.watermark_opacity = flag_value(&mut args, &arg)?,
+            "--focus-peak" => opts.focus_peak = Some(opts.focus_peak.unwrap_or(DEFAULT_FOCUS_PEAK_TOP)),
+            "--focus-peak-top" => opts.focus_peak = Some(flag_value(&mut args, &arg)?),
+            "--checker" => opts.convert.checker = true,
+            "--scroll-v" => {
+                opts.scroll_v = match flag_value(&mut args, &arg)? {
+                    speed if speed > 0.0 => Some(speed),
+                    _ => return Err("--scroll-v must be positive".into()),
+                };
+            }
+            "--scroll-end" => opts.scroll_end = flag_value(&mut args, &arg)?,
+            "--trim-duplicates" => opts.trim_duplicates = true,
+            "--portrait-fill" => opts.portrait_fill = Some(flag_value(&mut args, &arg)?),
+            "--cell-color" => opts.convert.cell_color = flag_value(&mut args, &arg)?,
+            "--rtl" => opts.convert.rtl = true,
+            "--colors" => opts.colors = flag_value(&mut args, &arg)?,
+            "--crossfade" => {
+                opts.crossfade = match flag_value(&mut args, &arg)? {
+                    0 => None,
+                    ms => Some(Duration::from_millis(ms)),
+                };
+            }
+            "--crt" => opts.crt = Some(opts.crt.unwrap_or(DEFAULT_CRT_INTENSITY)),
+            "--crt-intensity" => {
+                opts.crt = match flag_value(&mut args, &arg)? {
+                    intensity if (0.0..=1.0).contains(&intensity) => Some(intensity),
+                    _ => return Err("--crt-intensity must be between 0 and 1".into()),
+                };
+            }
+            "--crt-band" => {
+                opts.crt_band = true;
+                opts.crt = Some(opts.crt.unwrap_or(DEFAULT_CRT_INTENSITY));
+            }
+            "--inline-cursor" => opts.inline_cursor = true,
+            "--inline-rows" => {
+                opts.inline_rows = match flag_value(&mut args, &arg)? {
+                    0 => return Err("--inline-rows must be positive".into()),
+                    rows => rows,
+                };
+            }
+            "--thin-frames" => opts.thin_frames = flag_value(&mut args, &arg)?,
+            "--pause-at" => opts.pause_at = Some(flag_value(&mut args, &arg)?),
+            "--dot-scale" | "--color-scale" => {
+                let scale: f32 = flag_value(&mut args, &arg)?;
+                if !(scale > 0.0 && scale <= 8.0) {
+                    return Err(format!("{arg} must be between 0 and 8"));
+                }
+                match arg.as_str() {
+                    "--dot-scale" => opts.convert.dot_scale = scale,
+                    _ => opts.convert.color_scale = scale,
+                }
+            }
+            "--frame-delay" => {
+                let value: String = flag_value(&mut args, &arg)?;
+                match value.split_once(':').map(|(frame, ms)| (frame.parse(), ms.parse())) {
+                    Some((Ok(_), Ok(0))) => return Err(format!("--frame-delay {value}: the delay must be positive")),
+                    Some((Ok(frame), Ok(ms))) => opts.frame_delays.insert(frame, Duration::from_millis(ms)),
+                    _ => return Err(format!("invalid --frame-delay (expected FRAME:MS): {value}")),
+                };
+            }
+            "--term-size" => {
+                let value: String = flag_value(&mut args, &arg)?;
+                opts.term_size = match value.split_once('x').map(|(c, r)| (c.parse(), r.parse())) {
+                    Some((Ok(cols), Ok(rows))) if cols > 0 && rows > 0 => Some((cols, rows)),
+                    _ => return Err(format!("invalid --term-size (expected COLSxROWS): {value}")),
+                };
+            }
+            "--tile" => {
+                let value: String = flag_value(&mut args, &arg)?;
+                opts.tile = match value.split_once('x').map(|(c, r)| (c.parse::<u32>(), r.parse::<u32>())) {
+                    Some((Ok(cols), Ok(rows))) if cols > 0 && rows > 0 && cols.checked_mul(rows).is_some() => Some((cols, rows)),
+                    _ => return Err(format!("invalid --tile (expected COLSxROWS): {value}")),
+                };
+            }
+            "--tile-index" => opts.tile_index = flag_value(&mut args, &arg)?,
+            "--hold-on-unique" => {
+                opts.hold_on_unique = match flag_value(&mut args, &arg)? {
+                    factor if (0.0..=MAX_HOLD_ON_UNIQUE).contains(&factor) => Some(factor),
+                    _ => return Err(format!("--hold-on-unique must be between 0 and {MAX_HOLD_ON_UNIQUE}")),
+                };
+            }
+            "--dot-mask" => {
+                let value: String = flag_value(&mut args, &arg)?;
+                let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+                opts.convert.dot_mask = u8::from_str_radix(digits, 16).map_err(|_| format!("invalid --dot-mask: {value}"))?;
+            }
+            "--out" => opts.out_dir = Some(flag_value(&mut args, &arg)?),
+            "--glyph" => {
+                let value: String = flag_value(&mut args, &arg)?;
+                let digits = value.trim_start_matches("0b").trim_start_matches("0B");
+                opts.glyph = Some(u8::from_str_radix(digits, 2).map_err(|_| format!("invalid --glyph: {value}"))?);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {arg}")),
+            _ if gif_path.is_none() => gif_path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    if opts.glyph.is_some() {
+        return Ok(opts);
+    }
+    opts.gif_path = gif_path.ok_or("missing path to GIF")?;
+    if let Some((cols, rows)) = opts.tile {
+        if opts.tile_index >= tile_count((cols, rows)) {
+            return Err(format!("--tile-index {} is outside the {cols}x{rows} grid", opts.tile_index));
+        }
+    }
+    if opts.extract_frames.is_some() && opts.out_dir.is_none() {
+        return Err("--extract-frames needs --out <dir>".into());
+    }
+    Ok(opts)
+}
+
+/// Takes the value following `flag` and parses it.
+fn flag_value<T: FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T, String> {
+    let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+    value.parse().map_err(|_| format!("invalid {flag}: {value}"))
+}
+
+/// Reads a GIF from disk, merges partial frames, converts each to braille lines without
+/// distorting the original aspect ratio, using a **higher‐quality Lanczos3** filter.
+/// Also returns how many source frames were too thin for the pane (see `warn_thin`).
+fn load_and_convert_gif(path: &Path, opts: &Options) -> Result<(Vec<BrailleFrame<'static>>, usize), Box<dyn Error>> {
+    let cells = pane_cells(opts);
+    let convert_opts = ConvertOptions { max_cells: Some(cells), ..opts.convert.clone() };
+
+    // Stream: each source frame is decoded, converted and dropped before the next one,
+    // so only the compact braille lines stay in memory
+    let mut out_frames = Vec::new();
+    let mut thin = 0;
+    decode_sources(path, opts, |image, delay| {
+        if is_thin(&image, cells) {
+            thin += 1;
+            if opts.thin_frames == ThinFrames::Skip {
+                return;
+            }
+        }
+        out_frames.push(BrailleFrame { delay, ..convert_image(image, &convert_opts, opts.focus_peak) });
+    })?;
+    finish_frames(&mut out_frames, cells, opts);
+    Ok((out_frames, thin))
+}
+
+/// Says what happened to the `thin` frames of `path`; only call this before the terminal
+/// is taken over, since it writes to stderr.
+fn warn_thin(path: &Path, thin: usize, mode: ThinFrames) {
+    match (thin, mode) {
+        (0, _) => {}
+        (_, ThinFrames::Clamp) => eprintln!(
+            "Warning: {thin} frame(s) of {} are too thin for the pane and were stretched to 1 px; --thin-frames skip leaves them out",
+            path.display()
+        ),
+        (_, ThinFrames::Skip) => eprintln!("Skipped {thin} frame(s) of {} too thin to show without stretching", path.display()),
+    }
+}
+
+/// Whether fitting `image` into `cells` only works by clamping a side up to 1 px.
+fn is_thin(image: &RgbaImage, (cols, rows): (u32, u32)) -> bool {
+    let (width, height) = image.dimensions();
+    compute_scaled_dims_checked(width, height, cols * 2, rows * 4).1
+}
+
+/// Decodes every source frame of `path` and keeps them, so they can be converted again
+/// for another pane size.
+fn load_sources(path: &Path, opts: &Options) -> Result<Vec<SourceFrame>, Box<dyn Error>> {
+    let mut sources = Vec::new();
+    decode_sources(path, opts, |image, delay| sources.push((image, delay)))?;
+    Ok(sources)
+}
+
+/// Converts kept source frames for the pane size `pane_cells` reports now.
+fn convert_sources(sources: &[SourceFrame], opts: &Options) -> Vec<BrailleFrame<'static>> {
+    let cells = pane_cells(opts);
+    let convert_opts = ConvertOptions { max_cells: Some(cells), ..opts.convert.clone() };
+    let mut frames = sources
+        .iter()
+        .filter(|(image, _)| !(opts.thin_frames == ThinFrames::Skip && is_thin(image, cells)))
+        .map(|(image, delay)| BrailleFrame { delay: *delay, ..convert_image(image.clone(), &convert_opts, opts.focus_peak) })
+        .collect();
+    finish_frames(&mut frames, cells, opts);
+    frames
+}
+
+/// Decodes `path` frame by frame (a still image as one frame), passing each prepared
+/// source frame to `on_source` together with its delay.
+fn decode_sources(path: &Path, opts: &Options, mut on_source: impl FnMut(RgbaImage, Duration)) -> Result<(), Box<dyn Error>> {
+    // The logo is composited onto each source frame, so it gets converted along with it
+    let mut watermark = match &opts.watermark {
+        Some(logo_path) => Some(Watermark::new(image::open(logo_path)?.into_rgba8(), opts)),
+        None => None,
+    };
+    let mut prepare = |mut image: RgbaImage| {
+        if let Some(grid) = opts.tile {
+            image = crop_tile(&image, grid, opts.tile_index);
+        }
+        if let Some(watermark) = &mut watermark {
+            watermark.stamp(&mut image);
+        }
+        image
+    };
+
+    // Each frame keeps its own native delay; a still image is a single frame
+    let mut decimator = FrameDecimator::new(opts.every, opts.motion_blur);
+    for source in SourceFrames::open(path, opts.max_frame_dim)? {
+        let (image, delay) = source?;
+        if let Some((image, delay)) = decimator.push(image, delay) {
+            on_source(prepare(image), delay);
+        }
+    }
+    if let Some((image, delay)) = decimator.finish() {
+        on_source(prepare(image), delay);
+    }
+    Ok(())
+}
+
+/// The whole-animation passes run once every frame is converted for `cells`.
+fn finish_frames(frames: &mut Vec<BrailleFrame<'static>>, cells: (u32, u32), opts: &Options) {
+    if opts.trim_duplicates {
+        trim_duplicate_ends(frames);
+    }
+
+    if let Some(fill) = opts.portrait_fill {
+        let cols = cells.0 as usize;
+        for frame in frames.iter_mut() {
+            // `--focus-peak` cells move right along with the centered copy of their line
+            for (row, col) in &mut frame.peaks {
+                *col += frame.lines.get(*row).map_or(0, |line| fill_offset(line.spans.len(), cols));
+            }
+            fill_width(&mut frame.lines, cols, fill);
+        }
+    }
+
+    if let Some(source) = opts.static_color {
+        freeze_colors(frames, source);
+    }
+
+    if opts.detect_loop {
+        let (start, end) = find_loop(frames);
+        frames.truncate(end);
+        frames.drain(..start);
+    }
+
+    if let Some(factor) = opts.hold_on_unique {
+        hold_on_unique(frames, factor);
+    }
+}
+
+/// Largest `--hold-on-unique` factor, so a held delay always fits in a `Duration`.
+const MAX_HOLD_ON_UNIQUE: f64 = 100.0;
+
+/// Lengthens each frame's delay by how much it changed from the frame before it (the
+/// first one compares against the last, as in a loop): the most changed frame is held
+/// `1 + factor` times as long, frames that barely change keep their delay.
+fn hold_on_unique(frames: &mut [BrailleFrame<'static>], factor: f64) {
+    let n = frames.len();
+    if n < 2 {
+        return;
+    }
+    let differences: Vec<f64> = (0..n).map(|i| frame_difference(&frames[(i + n - 1) % n], &frames[i])).collect();
+    let most = differences.iter().copied().fold(0.0, f64::max);
+    if most <= 0.0 {
+        return;
+    }
+    for (frame, difference) in frames.iter_mut().zip(differences) {
+        frame.delay = frame.delay.mul_f64(1.0 + factor * difference / most);
+    }
+}
+
+/// How many tiles a `--tile` grid has.
+fn tile_count((cols, rows): (u32, u32)) -> usize {
+    cols as usize * rows as usize
+}
+
+/// Tile `index` (in reading order) of `image` cut into a `cols`×`rows` grid; edge tiles
+/// take up whatever the division leaves over.
+fn crop_tile(image: &RgbaImage, (cols, rows): (u32, u32), index: usize) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let (tile_w, tile_h) = (width.div_ceil(cols).max(1), height.div_ceil(rows).max(1));
+    let (col, row) = (index as u32 % cols, index as u32 / cols);
+    let (x, y) = ((col * tile_w).min(width.saturating_sub(1)), (row * tile_h).min(height.saturating_sub(1)));
+    imageops::crop_imm(image, x, y, tile_w.min(width - x), tile_h.min(height - y)).to_image()
+}
+
+/// Width of a `--watermark` logo as a fraction of the frame width.
+const WATERMARK_SCALE: f32 = 0.2;
+
+/// A logo alpha-composited into one corner of every frame.
+struct Watermark {
+    logo: RgbaImage,
+    corner: Corner,
+    opacity: f32,
+    /// The logo resized for the last frame size seen (every frame of a GIF shares one).
+    scaled: Option<RgbaImage>,
+}
+
+impl Watermark {
+    fn new(logo: RgbaImage, opts: &Options) -> Self {
+        Watermark {
+            logo,
+            corner: opts.watermark_pos,
+            opacity: opts.watermark_opacity.clamp(0.0, 1.0),
+            scaled: None,
+        }
+    }
+
+    /// Blends the logo over `frame`, scaled to `WATERMARK_SCALE` of its width and inset
+    /// from the chosen corner.
+    fn stamp(&mut self, frame: &mut RgbaImage) {
+        let (frame_w, frame_h) = frame.dimensions();
+        let (logo_w, logo_h) = self.logo.dimensions();
+        if logo_w == 0 || logo_h == 0 {
+            return;
+        }
+        let width = ((frame_w as f32 * WATERMARK_SCALE).round() as u32).max(1);
+        let height = ((logo_h as f32 * width as f32 / logo_w as f32).round() as u32).max(1);
+        if self.scaled.as_ref().map(|s| s.width()) != Some(width) {
+            self.scaled = Some(imageops::resize(&self.logo, width, height, imageops::FilterType::Triangle));
+        }
+        let Some(scaled) = &self.scaled else {
+            return;
+        };
+
+        let margin = frame_w.min(frame_h) / 40;
+        let left = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => margin as i64,
+            Corner::TopRight | Corner::BottomRight => frame_w as i64 - width as i64 - margin as i64,
+        };
+        let top = match self.corner {
+            Corner::TopLeft | Corner::TopRight => margin as i64,
+            Corner::BottomLeft | Corner::BottomRight => frame_h as i64 - height as i64 - margin as i64,
+        };
+
+        for (x, y, logo_px) in scaled.enumerate_pixels() {
+            let (fx, fy) = (left + x as i64, top + y as i64);
+            if fx < 0 || fy < 0 || fx >= frame_w as i64 || fy >= frame_h as i64 {
+                continue;
+            }
+            let alpha = logo_px[3] as f32 / 255.0 * self.opacity;
+            let dst = frame.get_pixel_mut(fx as u32, fy as u32);
+            for c in 0..3 {
+                dst[c] = (dst[c] as f32 * (1.0 - alpha) + logo_px[c] as f32 * alpha).round() as u8;
+            }
+            dst[3] = dst[3].max((alpha * 255.0).round() as u8);
+        }
+    }
+}
+
+/// Collapses runs of identical frames at the very start and end down to a single frame,
+/// leaving repeats in the middle (which are there for timing) alone.
+fn trim_duplicate_ends(frames: &mut Vec<BrailleFrame<'static>>) {
+    let leading = frames.windows(2).take_while(|pair| pair[0].lines == pair[1].lines).count();
+    frames.drain(..leading);
+    let trailing = frames.windows(2).rev().take_while(|pair| pair[0].lines == pair[1].lines).count();
+    frames.truncate(frames.len() - trailing);
+}
+
+/// How much of the edge color the `--portrait-fill center` gradient starts with.
+const SIDE_GRADIENT_STRENGTH: f32 = 0.35;
+
+/// How far `fill_width` moves a line `width` cells wide to center it in `cols`.
+fn fill_offset(width: usize, cols: usize) -> usize {
+    match width {
+        0 => 0,
+        width => cols.saturating_sub(width) / 2,
+    }
+}
+
+/// Uses the slack beside lines narrower than `cols` (portrait content in a wide pane):
+/// centered with a fading side gradient, tiled, or tiled with every other copy mirrored.
+fn fill_width(lines: &mut [Line<'static>], cols: usize, fill: PortraitFill) {
+    for line in lines {
+        let width = line.spans.len();
+        if width == 0 || width >= cols {
+            continue;
+        }
+        // One copy stays centered; tiles extend outward from it
+        let offset = fill_offset(width, cols);
+        let spans = std::mem::take(&mut line.spans);
+
+        line.spans = match fill {
+            PortraitFill::Center => {
+                let side = |edge: &Span<'static>, distance: usize, len: usize| {
+                    let fade = (1.0 - distance as f32 / (len + 1) as f32) * SIDE_GRADIENT_STRENGTH;
+                    match edge.style.bg.or(edge.style.fg) {
+                        Some(color) => {
+                            let [r, g, b] = color_to_rgb(color).map(|c| (c as f32 * fade).round() as u8);
+                            Span::styled(" ", Style::default().bg(Color::Rgb(r, g, b)))
+                        }
+                        None => Span::raw(" "),
+                    }
+                };
+                let right_len = cols - width - offset;
+                let mut out: Vec<Span<'static>> = (1..=offset).rev().map(|d| side(&spans[0], d, offset)).collect();
+                let right: Vec<Span<'static>> = (1..=right_len).map(|d| side(&spans[width - 1], d, right_len)).collect();
+                out.extend(spans);
+                out.extend(right);
+                out
+            }
+            PortraitFill::Tile | PortraitFill::Mirror => (0..cols)
+                .map(|col| {
+                    let pos = col as isize - offset as isize;
+                    let (copy, i) = (pos.div_euclid(width as isize), pos.rem_euclid(width as isize) as usize);
+                    if fill == PortraitFill::Mirror && copy % 2 != 0 {
+                        let span = &spans[width - 1 - i];
+                        Span::styled(mirror_glyph(&span.content), span.style)
+                    } else {
+                        spans[i].clone()
+                    }
+                })
+                .collect(),
+        };
+    }
+}
+
+/// Braille cells available inside one pane's border, from `--term-size` or the terminal.
+fn pane_cells(opts: &Options) -> (u32, u32) {
+    let (term_cols, term_rows) = opts
+        .term_size
+        .unwrap_or_else(|| size_or_default(crossterm::terminal::size()));
+    // Side-by-side mode gives each GIF half of the width
+    let pane_cols = if opts.compare_path.is_some() { term_cols / 2 } else { term_cols };
+    // Scrolling output only has to fit the width; its height is what gets scrolled through
+    let pane_rows = match (opts.scroll_v, opts.inline_cursor) {
+        (Some(_), _) => SCROLL_MAX_ROWS,
+        (None, true) => (term_rows as u32).saturating_sub(2).min(opts.inline_rows),
+        (None, false) => (term_rows as u32).saturating_sub(2),
+    };
+    ((pane_cols as u32).saturating_sub(2), pane_rows)
+}
+
+/// Rows of cells `--inline-cursor` plays in unless `--inline-rows` says otherwise.
+const DEFAULT_INLINE_ROWS: u32 = 12;
+
+/// Tallest rendering `--scroll-v` converts to, in cells.
+const SCROLL_MAX_ROWS: u32 = 4096;
+
+/// Leading bytes of a `--save-cache` file.
+const CACHE_MAGIC: &[u8; 4] = b"GBTC";
+/// Bumped whenever the cache layout changes; older files are rejected, not misread.
+const CACHE_VERSION: u16 = 3;
+
+/// Writes converted frames as a compact binary dump (little-endian throughout):
+/// magic, version, pane size, then per frame its delay, lines of
+/// `(glyph, fg, bg, modifiers)` spans and its `--focus-peak` `(row, col)` cells.
+fn write_cache(frames: &[BrailleFrame<'static>], cells: (u32, u32), path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    out.write_all(CACHE_MAGIC)?;
+    out.write_all(&CACHE_VERSION.to_le_bytes())?;
+    out.write_all(&cells.0.to_le_bytes())?;
+    out.write_all(&cells.1.to_le_bytes())?;
+    out.write_all(&(frames.len() as u32).to_le_bytes())?;
+
+    for frame in frames {
+        let delay_ms = frame.delay.as_millis().min(u32::MAX as u128) as u32;
+        out.write_all(&delay_ms.to_le_bytes())?;
+        out.write_all(&(frame.lines.len() as u32).to_le_bytes())?;
+        for line in &frame.lines {
+            out.write_all(&(line.spans.len() as u32).to_le_bytes())?;
+            for span in &line.spans {
+                out.write_all(&(span.content.len() as u16).to_le_bytes())?;
+                out.write_all(span.content.as_bytes())?;
+                out.write_all(&encode_color(span.style.fg))?;
+                out.write_all(&encode_color(span.style.bg))?;
+                out.write_all(&span.style.add_modifier.bits().to_le_bytes())?;
+            }
+        }
+        out.write_all(&(frame.peaks.len() as u32).to_le_bytes())?;
+        for &(row, col) in &frame.peaks {
+            out.write_all(&(row as u32).to_le_bytes())?;
+            out.write_all(&(col as u32).to_le_bytes())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// 64-bit FNV-1a over a frame's glyphs, colors and modifiers (not its delay), the same
+/// on every platform and build so `--checksums` output can be diffed across versions.
+fn frame_checksum(frame: &BrailleFrame) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    };
+    for line in &frame.lines {
+        // Row separator, so the same cells wrapped differently hash differently
+        feed(b"\n");
+        for span in &line.spans {
+            feed(span.content.as_bytes());
+            feed(&encode_color(span.style.fg));
+            feed(&encode_color(span.style.bg));
+            feed(&span.style.add_modifier.bits().to_le_bytes());
+        }
+    }
+    hash
+}
+
+/// Reads frames back from a `--save-cache` file, refusing ones converted for a different
+/// pane size since they would be cropped or leave the pane half empty.
+fn read_cache(path: &Path, cells: (u32, u32)) -> Result<Vec<BrailleFrame<'static>>, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let mut reader = CacheReader { bytes: &bytes, pos: 0 };
+
+    if reader.take(4)? != CACHE_MAGIC {
+        return Err(format!("{} is not a frame cache", path.display()).into());
+    }
+    let version = reader.u16()?;
+    if version != CACHE_VERSION {
+        return Err(format!("{}: unsupported cache version {version} (expected {CACHE_VERSION})", path.display()).into());
+    }
+    let cached = (reader.u32()?, reader.u32()?);
+    if cached != cells {
+        return Err(format!(
+            "{} was made for a {}x{} pane but this one is {}x{}; recreate it with --save-cache",
+            path.display(),
+            cached.0,
+            cached.1,
+            cells.0,
+            cells.1
+        )
+        .into());
+    }
+
+    let frame_count = reader.u32()?;
+    let mut frames = Vec::with_capacity(frame_count.min(1 << 16) as usize);
+    for _ in 0..frame_count {
+        let delay = Duration::from_millis(reader.u32()? as u64);
+        let line_count = reader.u32()?;
+        let mut lines = Vec::new();
+        for _ in 0..line_count {
+            let span_count = reader.u32()?;
+            let mut spans = Vec::new();
+            for _ in 0..span_count {
+                let len = reader.u16()? as usize;
+                let content = String::from_utf8(reader.take(len)?.to_vec())?;
+                let fg = decode_color(reader.take(4)?)?;
+                let bg = decode_color(reader.take(4)?)?;
+                let mut style = Style::default().add_modifier(Modifier::from_bits_truncate(reader.u16()?));
+                style.fg = fg;
+                style.bg = bg;
+                spans.push(Span::styled(content, style));
+            }
+            lines.push(Line::from(spans));
+        }
+        let peak_count = reader.u32()?;
+        let mut peaks = Vec::new();
+        for _ in 0..peak_count {
+            peaks.push((reader.u32()? as usize, reader.u32()? as usize));
+        }
+        frames.push(BrailleFrame { lines, delay, peaks });
+    }
+    Ok(frames)
+}
+
+/// Bounds-checked cursor over the bytes of a cache file.
+struct CacheReader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> CacheReader<'b> {
+    fn take(&mut self, n: usize) -> Result<&'b [u8], String> {
+        let bytes = self.bytes.get(self.pos..self.pos + n).ok_or("cache file is truncated")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+/// Packs an optional cell color into a tag byte plus three payload bytes.
+fn encode_color(color: Option<Color>) -> [u8; 4] {
+    if let Some(i) = color.and_then(|color| ANSI16.iter().position(|&(named, _)| named == color)) {
+        return [3, i as u8, 0, 0];
+    }
+    match color {
+        None => [0, 0, 0, 0],
+        Some(Color::Indexed(i)) => [2, i, 0, 0],
+        Some(color) => {
+            let [r, g, b] = color_to_rgb(color);
+            [1, r, g, b]
+        }
+    }
+}
+
+fn decode_color(bytes: &[u8]) -> Result<Option<Color>, String> {
+    match *bytes {
+        [0, ..] => Ok(None),
+        [1, r, g, b] => Ok(Some(Color::Rgb(r, g, b))),
+        [2, i, ..] => Ok(Some(Color::Indexed(i))),
+        [3, i @ 0..=15, ..] => Ok(Some(ANSI16[i as usize].0)),
+        _ => Err(format!("corrupt color in cache file (tag {})", bytes[0])),
+    }
+}
+
+/// Repaints every frame with one fixed set of per-cell colors (taken from the first frame
+/// or averaged over all of them), leaving only the dot patterns animated.
+fn freeze_colors(frames: &mut [BrailleFrame<'static>], source: StaticColor) {
+    let Some(first) = frames.first() else {
+        return;
+    };
+    let cell_styles = |frame: &BrailleFrame<'static>| -> Vec<Vec<(Option<Color>, Option<Color>)>> {
+        frame
+            .lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| (span.style.fg, span.style.bg)).collect())
+            .collect()
+    };
+
+    let colors = match source {
+        StaticColor::First => cell_styles(first),
+        StaticColor::Average => {
+            // Per-cell running sums for foreground and background
+            let mut sums: Vec<Vec<(ColorSum, ColorSum)>> = first
+                .lines
+                .iter()
+                .map(|line| vec![(ColorSum::default(), ColorSum::default()); line.spans.len()])
+                .collect();
+            for frame in frames.iter() {
+                for (row, line) in sums.iter_mut().zip(&frame.lines) {
+                    for ((fg_sum, bg_sum), span) in row.iter_mut().zip(&line.spans) {
+                        if let Some(fg) = span.style.fg {
+                            let [r, g, b] = color_to_rgb(fg);
+                            fg_sum.add(r, g, b);
+                        }
+                        if let Some(bg) = span.style.bg {
+                            let [r, g, b] = color_to_rgb(bg);
+                            bg_sum.add(r, g, b);
+                        }
+                    }
+                }
+            }
+            sums.iter()
+                .map(|row| row.iter().map(|(fg, bg)| (fg.average(), bg.average())).collect())
+                .collect()
+        }
+    };
+
+    for frame in frames.iter_mut() {
+        for (line, row) in frame.lines.iter_mut().zip(&colors) {
+            for (span, &(fg, bg)) in line.spans.iter_mut().zip(row) {
+                span.style.fg = fg;
+                span.style.bg = bg;
+            }
+        }
+    }
+}
+
+/// Finds the `start..end` range that loops most seamlessly: the seam it jumps across, from
+/// its last frame back to its first, should change the picture as little as possible.
+/// Starts are searched in the first half so intros get cut, loops span at least half the
+/// file (neighboring frames always make a smooth seam), and ties favor longer loops, so
+/// the whole file is kept unless a shorter loop has a smoother seam.
+fn find_loop(frames: &[BrailleFrame<'static>]) -> (usize, usize) {
+    let mut best = (0, frames.len());
+    let mut best_diff = match frames {
+        [first, .., last] => frame_difference(last, first),
+        _ => return best,
+    };
+    for start in 0..frames.len() / 2 {
+        for end in start + (frames.len() / 2).max(2)..=frames.len() {
+            let diff = frame_difference(&frames[end - 1], &frames[start]);
+            if diff < best_diff || (diff == best_diff && end - start > best.1 - best.0) {
+                best = (start, end);
+                best_diff = diff;
+            }
+        }
+    }
+    best
+}
+
+/// How different two converted frames look, from 0.0 (identical) to 1.0, averaging
+/// per-cell color distance and the share of dots that differ.
+fn frame_difference(a: &BrailleFrame<'static>, b: &BrailleFrame<'static>) -> f64 {
+    let mut total = 0.0;
+    let mut count = 0;
+    for ((glyph_a, rgb_a), (glyph_b, rgb_b)) in frame_cells(a).zip(frame_cells(b)) {
+        let color = rgb_a
+            .iter()
+            .zip(&rgb_b)
+            .map(|(&x, &y)| (x as f64 - y as f64).abs())
+            .sum::<f64>()
+            / (3.0 * 255.0);
+        let dots = if glyph_a == glyph_b {
+            0.0
+        } else if (0x2800..=0x28FF).contains(&glyph_a) && (0x2800..=0x28FF).contains(&glyph_b) {
+            (glyph_a ^ glyph_b).count_ones() as f64 / 8.0
+        } else {
+            1.0
+        };
+        total += (color + dots) / 2.0;
+        count += 1;
+    }
+    let size_mismatch = a.lines.len() != b.lines.len();
+    match count {
+        0 => f64::from(size_mismatch),
+        n => total / n as f64,
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // 1) Parse CLI argument: path to GIF
-    let mut args = std::env::args().skip(1);
-    let gif_path = match args.next() {
-        Some(path) => PathBuf::from(path),
-        None => {
-            eprintln!("Usage: gif_braille_tui <path_to_gif>");
-            std::process::exit(1);
+/// Each cell of a frame as (glyph code point, foreground RGB), row by row.
+fn frame_cells<'f>(frame: &'f BrailleFrame<'static>) -> impl Iterator<Item = (u32, [u8; 3])> + 'f {
+    frame.lines.iter().flat_map(|line| line.spans.iter()).map(|span| {
+        let glyph = span.content.chars().next().unwrap_or(' ') as u32;
+        (glyph, color_to_rgb(span.style.fg.unwrap_or(Color::Reset)))
+    })
+}
+
+/// Terminal size to convert for when there is no TTY to ask (pipes, CI).
+const FALLBACK_TERM_SIZE: (u16, u16) = (80, 24);
+
+/// Uses the queried terminal size, or `FALLBACK_TERM_SIZE` when the query failed or
+/// reported an empty terminal, so headless exports still work.
+fn size_or_default(queried: io::Result<(u16, u16)>) -> (u16, u16) {
+    match queried {
+        Ok((cols, rows)) if cols > 0 && rows > 0 => (cols, rows),
+        _ => FALLBACK_TERM_SIZE,
+    }
+}
+
+/// How many times an input plays by its own metadata, `None` meaning forever. Only GIF
+/// loop counts are read; other animations loop forever, stills show once.
+fn source_plays(bytes: &[u8]) -> Option<u32> {
+    if bytes.starts_with(b"GIF") {
+        scan_gif(bytes).plays()
+    } else if is_animation(bytes) {
+        None
+    } else {
+        Some(1)
+    }
+}
+
+/// Writes the frames selected by `range` to `out_dir` as full-resolution PNGs (a still
+/// image is frame 0), decoding no further than the range reaches.
+fn extract_frames(path: &Path, range: &FrameRange, out_dir: &Path, opts: &Options) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(out_dir)?;
+    let wanted = range.last().map_or(usize::MAX, |last| last.saturating_add(1));
+    for (i, frame) in SourceFrames::open(path, opts.max_frame_dim)?.enumerate().take(wanted) {
+        let (image, _) = frame?;
+        if range.contains(i) {
+            image.save(out_dir.join(format!("frame_{i:04}.png")))?;
         }
-    };
+    }
+    Ok(())
+}
 
-    // 2) Decode + convert all frames into braille/color lines
-    let frames = load_and_convert_gif(&gif_path)?;
-    if frames.is_empty() {
-        eprintln!("No frames found or failed to decode GIF.");
-        std::process::exit(1);
+/// Thins a stream of frames down to one of every `every`. With `blend`, each kept frame
+/// is the per-pixel average of its whole group, so dropped motion shows up as blur.
+/// Either way a kept frame stays up for its whole group's delay, so playback speed is
+/// unchanged.
+struct FrameDecimator {
+    every: usize,
+    blend: bool,
+    seen: usize,
+    /// First frame of the group, kept as is when not blending.
+    first: Option<RgbaImage>,
+    /// Per-channel running totals for the group being blended.
+    sum: Vec<u32>,
+    dims: (u32, u32),
+    /// Combined delay of the frames in the group so far.
+    delay: Duration,
+}
+
+impl FrameDecimator {
+    fn new(every: usize, blend: bool) -> Self {
+        FrameDecimator {
+            every: every.max(1),
+            blend: blend && every > 1,
+            seen: 0,
+            first: None,
+            sum: Vec::new(),
+            dims: (0, 0),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Feeds the next frame, returning a frame and its delay once one is ready to keep.
+    fn push(&mut self, frame: RgbaImage, delay: Duration) -> Option<(RgbaImage, Duration)> {
+        let index = self.seen;
+        self.seen += 1;
+        self.delay += delay;
+
+        if index.is_multiple_of(self.every) {
+            self.dims = frame.dimensions();
+            if self.blend {
+                self.sum = vec![0; frame.as_raw().len()];
+            }
+        }
+        if self.blend {
+            for (acc, &c) in self.sum.iter_mut().zip(frame.as_raw()) {
+                *acc += c as u32;
+            }
+        } else if index.is_multiple_of(self.every) {
+            self.first = Some(frame);
+        }
+
+        if self.seen.is_multiple_of(self.every) {
+            self.take(self.every)
+        } else {
+            None
+        }
     }
 
-    // 3) Set up terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    /// Flushes a partially filled final group.
+    fn finish(mut self) -> Option<(RgbaImage, Duration)> {
+        match self.seen % self.every {
+            0 => None,
+            partial => self.take(partial),
+        }
+    }
 
-    // 4) Run the TUI loop to display frames at ~60 fps
-    let res = run_app(&mut terminal, &frames);
+    fn take(&mut self, count: usize) -> Option<(RgbaImage, Duration)> {
+        let delay = std::mem::take(&mut self.delay);
+        if !self.blend {
+            return self.first.take().map(|frame| (frame, delay));
+        }
+        let (width, height) = self.dims;
+        let pixels = std::mem::take(&mut self.sum).into_iter().map(|c| (c / count as u32) as u8).collect();
+        RgbaImage::from_raw(width, height, pixels).map(|frame| (frame, delay))
+    }
+}
 
-    // 5) Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+/// Writes an asciinema v2 recording that plays the frames once with their real timing.
+fn write_cast(frames: &[BrailleFrame<'static>], opts: &Options, path: &Path) -> Result<(), Box<dyn Error>> {
+    let width = frames.iter().flat_map(|f| &f.lines).map(Line::width).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.lines.len()).max().unwrap_or(0);
+    let delays = playback_delays(frames, opts);
 
-    if let Err(err) = res {
-        eprintln!("Error: {err:?}");
+    let mut out = io::BufWriter::new(File::create(path)?);
+    writeln!(out, r#"{{"version": 2, "width": {width}, "height": {height}}}"#)?;
+    let mut at = Duration::ZERO;
+    for (frame, delay) in frames.iter().zip(&delays) {
+        // Home the cursor and repaint the whole frame each time
+        let data = format!("\x1b[H{}", frame_to_ansi(frame, opts.export_colorspace));
+        writeln!(out, r#"[{:.6}, "o", "{}"]"#, at.as_secs_f64(), json_escape(&data))?;
+        at += *delay;
     }
+    // Clear formatting at the end so the last frame stays up for its full delay
+    writeln!(out, r#"[{:.6}, "o", "{}"]"#, at.as_secs_f64(), json_escape("\x1b[0m"))?;
+    out.flush()?;
     Ok(())
 }
 
-/// Reads a GIF from disk, merges partial frames, converts each to braille lines without
-/// distorting the original aspect ratio, using a **higher‐quality Lanczos3** filter.
-fn load_and_convert_gif(path: &PathBuf) -> Result<Vec<BrailleFrame<'static>>, Box<dyn Error>> {
-    let file_in = File::open(path)?;
-    let decoder = GifDecoder::new(file_in)?;
-    let frames_iter = decoder.into_frames().collect_frames()?;
-
-    // Query terminal size, compute max braille cells => max pixel dims
-    let (term_cols, term_rows) = crossterm::terminal::size()?;
-    let max_braille_cols = (term_cols as u32).saturating_sub(2);
-    let max_braille_rows = (term_rows as u32).saturating_sub(2);
-    let max_width_px = max_braille_cols * 2;
-    let max_height_px = max_braille_rows * 4;
-
-    let mut out_frames = Vec::with_capacity(frames_iter.len());
-
-    for frame in frames_iter {
-        let rgba = frame.buffer();
-        let width = rgba.width();
-        let height = rgba.height();
-        let Some(image) = RgbaImage::from_raw(width, height, rgba.clone().into_raw()) else {
-            continue;
-        };
+/// Renders a frame as text with ANSI color escapes, one terminal row per line. Colors keep
+/// their kind (see `sgr_color`), so a `--colors 16` cast only needs a 16-color terminal.
+fn frame_to_ansi(frame: &BrailleFrame<'static>, colorspace: ExportColorspace) -> String {
+    let mut out = String::new();
+    for (i, line) in frame.lines.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\r\n");
+        }
+        for span in &line.spans {
+            out.push_str("\x1b[0m");
+            if let Some(fg) = span.style.fg {
+                out.push_str(&sgr_color(fg, false, colorspace));
+            }
+            if let Some(bg) = span.style.bg {
+                out.push_str(&sgr_color(bg, true, colorspace));
+            }
+            if span.style.add_modifier.contains(Modifier::BOLD) {
+                out.push_str("\x1b[1m");
+            }
+            out.push_str(&span.content);
+        }
+    }
+    out.push_str("\x1b[0m");
+    out
+}
 
-        // -- Keep aspect ratio --
-        let (new_width, new_height) = compute_scaled_dims(width, height, max_width_px, max_height_px);
+/// The escape selecting `color` as the foreground (or `background`): 30-37 / 90-97 for the
+/// 16 standard colors, `38;5;n` for 256-color indices and `38;2;r;g;b` for anything else.
+fn sgr_color(color: Color, background: bool, colorspace: ExportColorspace) -> String {
+    let (base, extended) = if background { (40, 48) } else { (30, 38) };
+    if let Some(i) = ANSI16.iter().position(|&(named, _)| named == color) {
+        // `ANSI16` is in SGR order; the bright half starts 60 codes later
+        let code = if i < 8 { base + i } else { base + 60 + i - 8 };
+        return format!("\x1b[{code}m");
+    }
+    match color {
+        Color::Indexed(i) => format!("\x1b[{extended};5;{i}m"),
+        color => {
+            let [r, g, b] = export_rgb(color, colorspace);
+            format!("\x1b[{extended};2;{r};{g};{b}m")
+        }
+    }
+}
 
-        let resized = if new_width > 0 && new_height > 0 {
-            imageops::resize(
-                &image,
-                new_width,
-                new_height,
-                // Higher‐quality filter for smoother downscaling
-                imageops::FilterType::Lanczos3,
-            )
-        } else {
-            ImageBuffer::<Rgba<u8>, _>::new(1, 1)
-        };
+/// Escapes `s` for use inside a JSON string literal.
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
 
-        // Convert to braille + color lines
-        let braille_lines = rgba_to_braille_colored(resized);
-        out_frames.push(BrailleFrame { lines: braille_lines });
-    }
-
-    Ok(out_frames)
-}
-
-/// Compute new dimensions for the image, preserving aspect ratio,
-/// so it fits within (max_w, max_h).
-fn compute_scaled_dims(
-    orig_w: u32,
-    orig_h: u32,
-    max_w: u32,
-    max_h: u32,
-) -> (u32, u32) {
-    if max_w == 0 || max_h == 0 || orig_w == 0 || orig_h == 0 {
-        return (0, 0);
-    }
-
-    let orig_w_f = orig_w as f32;
-    let orig_h_f = orig_h as f32;
-    let max_w_f = max_w as f32;
-    let max_h_f = max_h as f32;
-
-    // scale factor to fit width
-    let scale_w = max_w_f / orig_w_f;
-    // scale factor to fit height
-    let scale_h = max_h_f / orig_h_f;
-    let scale = scale_w.min(scale_h);
-
-    // if the image is smaller already, skip upscaling
-    let scale = scale.min(1.0);
-
-    let new_w = (orig_w_f * scale).round().max(1.0) as u32;
-    let new_h = (orig_h_f * scale).round().max(1.0) as u32;
-    (new_w, new_h)
-}
-
-/// Convert an RGBA image into multi‐line braille cells with 24‐bit color.
-fn rgba_to_braille_colored(img: RgbaImage) -> Vec<Line<'static>> {
-    let width = img.width();
-    let height = img.height();
-
-    // Each braille cell is 2 px wide, 4 px tall
-    let cell_cols = (width + 1) / 2;
-    let cell_rows = (height + 3) / 4;
-
-    let mut lines = Vec::with_capacity(cell_rows as usize);
-
-    for row in 0..cell_rows {
-        let mut span_vec = Vec::with_capacity(cell_cols as usize);
-
-        for col in 0..cell_cols {
-            let mut r_sum: u32 = 0;
-            let mut g_sum: u32 = 0;
-            let mut b_sum: u32 = 0;
-            let mut count: u32 = 0;
-            let mut dots: u8 = 0;
-
-            for sub_row in 0..4 {
-                for sub_col in 0..2 {
-                    let px_x = col * 2 + sub_col;
-                    let px_y = row * 4 + sub_row;
-
-                    if px_x < width && px_y < height {
-                        let Rgba([r, g, b, a]) = *img.get_pixel(px_x, px_y);
-
-                        // Map (sub_col, sub_row) => braille bit
-                        let bit_index = match (sub_col, sub_row) {
-                            (0, 0) => 0, // dot1
-                            (0, 1) => 1, // dot2
-                            (0, 2) => 2, // dot3
-                            (0, 3) => 3, // dot7
-                            (1, 0) => 4, // dot4
-                            (1, 1) => 5, // dot5
-                            (1, 2) => 6, // dot6
-                            (1, 3) => 7, // dot8
-                            _ => 0,
-                        };
+/// Size of one braille dot (and the gap around it) in a rasterized frame.
+const DOT_PX: u32 = 4;
+/// Spacing between frames in the sprite sheet.
+const SHEET_GAP_PX: u32 = 8;
 
-                        // Simple brightness threshold
-                        let lum = 0.2126 * (r as f32)
-                            + 0.7152 * (g as f32)
-                            + 0.0722 * (b as f32);
-                        if a > 50 && lum > 20.0 {
-                            dots |= 1 << bit_index;
-                        }
+/// Rasterizes every frame and lays them out in a grid, `cols` frames per row.
+fn write_sprite_sheet(
+    frames: &[BrailleFrame<'static>],
+    cols: Option<u32>,
+    colorspace: ExportColorspace,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let rendered: Vec<RgbaImage> = frames.iter().map(|frame| rasterize_frame(frame, colorspace)).collect();
+    let count = rendered.len() as u32;
+    let cols = cols
+        .unwrap_or_else(|| (count as f32).sqrt().ceil() as u32)
+        .clamp(1, count.max(1));
+    let rows = count.div_ceil(cols);
+
+    let cell_w = rendered.iter().map(|img| img.width()).max().unwrap_or(1);
+    let cell_h = rendered.iter().map(|img| img.height()).max().unwrap_or(1);
+    let sheet_w = cols * cell_w + (cols + 1) * SHEET_GAP_PX;
+    let sheet_h = rows * cell_h + (rows + 1) * SHEET_GAP_PX;
+    let mut sheet = RgbaImage::from_pixel(sheet_w, sheet_h, Rgba([0, 0, 0, 255]));
+
+    for (i, img) in rendered.iter().enumerate() {
+        let (col, row) = (i as u32 % cols, i as u32 / cols);
+        let x = SHEET_GAP_PX + col * (cell_w + SHEET_GAP_PX);
+        let y = SHEET_GAP_PX + row * (cell_h + SHEET_GAP_PX);
+        imageops::overlay(&mut sheet, img, x as i64, y as i64);
+    }
+
+    sheet.save(path)?;
+    Ok(())
+}
 
-                        r_sum += r as u32;
-                        g_sum += g as u32;
-                        b_sum += b as u32;
-                        count += 1;
+/// Draws a braille frame as an image: each lit dot becomes a small square in its span's color,
+/// on top of the span's background color if it has one.
+fn rasterize_frame(frame: &BrailleFrame<'static>, colorspace: ExportColorspace) -> RgbaImage {
+    let cols = frame.lines.iter().map(|line| line.width() as u32).max().unwrap_or(0);
+    let rows = frame.lines.len() as u32;
+    let mut img = RgbaImage::from_pixel(
+        (cols * 2 * DOT_PX).max(1),
+        (rows * 4 * DOT_PX).max(1),
+        Rgba([0, 0, 0, 255]),
+    );
+
+    for (row, line) in frame.lines.iter().enumerate() {
+        let chars = line.spans.iter().flat_map(|span| {
+            let [r, g, b] = export_rgb(span.style.fg.unwrap_or(Color::White), colorspace);
+            let bg = span.style.bg.map(|bg| {
+                let [r, g, b] = export_rgb(bg, colorspace);
+                Rgba([r, g, b, 255])
+            });
+            span.content.chars().map(move |c| (c, Rgba([r, g, b, 255]), bg))
+        });
+        for (col, (c, color, bg)) in chars.enumerate() {
+            if let Some(bg) = bg {
+                fill_cell(&mut img, col as u32, row as u32, bg);
+            }
+            // `--mode blocks` / `--mode quadrant` glyphs
+            if let Some(mask) = QUADRANT_GLYPHS.iter().position(|g| g.starts_with(c)) {
+                for quad in (0..4).filter(|quad| mask & (1 << quad) != 0) {
+                    let x0 = (col as u32 * 2 + quad % 2) * DOT_PX;
+                    let y0 = (row as u32 * 2 + quad / 2) * 2 * DOT_PX;
+                    for y in y0..y0 + 2 * DOT_PX {
+                        for x in x0..x0 + DOT_PX {
+                            img.put_pixel(x, y, color);
+                        }
+                    }
+                }
+                continue;
+            }
+            let dots = (c as u32).wrapping_sub(0x2800);
+            if dots > 0xFF {
+                continue;
+            }
+            for (sub_col, bits) in (0..).zip(BRAILLE_DOT_BITS) {
+                for (sub_row, bit) in (0..).zip(bits) {
+                    if dots & (1 << bit) == 0 {
+                        continue;
+                    }
+                    let x0 = (col as u32 * 2 + sub_col) * DOT_PX;
+                    let y0 = (row as u32 * 4 + sub_row) * DOT_PX;
+                    for y in y0 + 1..y0 + DOT_PX - 1 {
+                        for x in x0 + 1..x0 + DOT_PX - 1 {
+                            img.put_pixel(x, y, color);
+                        }
                     }
                 }
             }
+        }
+    }
 
-            let braille_char = char::from_u32(0x2800 + dots as u32).unwrap_or(' ');
-            let (avg_r, avg_g, avg_b) = if count > 0 {
-                ((r_sum / count) as u8, (g_sum / count) as u8, (b_sum / count) as u8)
-            } else {
-                (0, 0, 0)
-            };
+    img
+}
+
+/// Paints the whole area of the cell at (`col`, `row`) in a rasterized frame.
+fn fill_cell(img: &mut RgbaImage, col: u32, row: u32, color: Rgba<u8>) {
+    let (x0, y0) = (col * 2 * DOT_PX, row * 4 * DOT_PX);
+    for y in y0..y0 + 4 * DOT_PX {
+        for x in x0..x0 + 2 * DOT_PX {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// RGB value of `color` in the requested export encoding.
+fn export_rgb(color: Color, colorspace: ExportColorspace) -> [u8; 3] {
+    let rgb = color_to_rgb(color);
+    match colorspace {
+        ExportColorspace::Srgb => rgb,
+        ExportColorspace::Linear => rgb.map(|c| (srgb_to_linear(c) * 255.0).round() as u8),
+    }
+}
 
-            // “Leak” the single‐char string to get 'static lifetime
-            let content: &'static str = Box::leak(braille_char.to_string().into_boxed_str());
+/// Short name for a GIF path, used in pane titles.
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Redraw interval while a `--crossfade` is in progress.
+const CROSSFADE_TICK: Duration = Duration::from_millis(16);
 
-            // Create a colored span
-            let span = Span::styled(
-                content,
-                Style::default()
-                    .fg(Color::Rgb(avg_r, avg_g, avg_b))
-                    .add_modifier(Modifier::BOLD),
-            );
-            span_vec.push(span);
+/// `to` with every cell color moved fraction `t` of the way from `from`'s color in the same
+/// cell; glyphs are always `to`'s. Blend truecolor frames: colors are re-encoded for `mode`
+/// afterwards, as `crt_frame` does.
+fn blend_frames(from: &BrailleFrame<'static>, to: &BrailleFrame<'static>, t: f32, mode: ColorMode) -> BrailleFrame<'static> {
+    let blend = |a: Option<Color>, b: Option<Color>| match (a, b) {
+        (Some(a), Some(b)) => {
+            let ([r1, g1, b1], [r2, g2, b2]) = (color_to_rgb(a), color_to_rgb(b));
+            let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+            mode.apply(Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2)))
         }
+        (_, b) => mode.apply(b?),
+    };
+    let lines = to
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(row, line)| {
+            let spans: Vec<Span<'static>> = line
+                .spans
+                .iter()
+                .enumerate()
+                .map(|(col, span)| {
+                    let mut span = span.clone();
+                    if let Some(old) = from.lines.get(row).and_then(|line| line.spans.get(col)) {
+                        span.style.fg = blend(old.style.fg, span.style.fg);
+                        span.style.bg = blend(old.style.bg, span.style.bg);
+                    }
+                    span
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    BrailleFrame { lines, delay: to.delay, peaks: to.peaks.clone() }
+}
+
+/// Scanline dimming `--crt` uses unless `--crt-intensity` says otherwise.
+const DEFAULT_CRT_INTENSITY: f32 = 0.3;
+
+/// How much the `--crt-band` band brightens the rows under it, and its height as a share
+/// of the frame.
+const CRT_BAND_BOOST: f32 = 0.25;
+const CRT_BAND_HEIGHT: f32 = 0.15;
+
+/// Redraw interval while the `--crt-band` band rolls.
+const CRT_TICK: Duration = Duration::from_millis(33);
+
+/// `frame` with every other row dimmed by `intensity` like CRT scanlines and, with `band`
+/// (its center as a fraction of the frame height), rows near the band brightened. Colors
+/// are re-encoded for `mode` afterwards.
+fn crt_frame(frame: &BrailleFrame<'static>, intensity: f32, band: Option<f32>, mode: ColorMode) -> BrailleFrame<'static> {
+    let rows = frame.lines.len().max(1) as f32;
+    let lines = frame
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(row, line)| {
+            let mut gain = if row % 2 == 1 { 1.0 - intensity } else { 1.0 };
+            if let Some(band) = band {
+                // Distance wraps around so the band rolls off the bottom onto the top
+                let offset = ((row as f32 + 0.5) / rows - band).rem_euclid(1.0);
+                let distance = offset.min(1.0 - offset) / CRT_BAND_HEIGHT;
+                gain *= 1.0 + CRT_BAND_BOOST * (-distance * distance).exp();
+            }
+            let shade = |color: Option<Color>| {
+                let [r, g, b] = color_to_rgb(color?);
+                let scale = |c: u8| (c as f32 * gain).round().min(255.0) as u8;
+                mode.apply(Color::Rgb(scale(r), scale(g), scale(b)))
+            };
+            let spans: Vec<Span<'static>> = line
+                .spans
+                .iter()
+                .map(|span| {
+                    let mut span = span.clone();
+                    span.style.fg = shade(span.style.fg);
+                    span.style.bg = shade(span.style.bg);
+                    span
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    BrailleFrame { lines, delay: frame.delay, peaks: frame.peaks.clone() }
+}
 
-        lines.push(Line::from(span_vec));
+/// Fastest `--scroll-v` redraws, so slow scrolls don't redraw for nothing.
+const SCROLL_MIN_TICK: Duration = Duration::from_millis(16);
+
+/// What `--scroll-v` does once the bottom of the rendering is in view.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScrollEnd {
+    Stop,
+    Loop,
+}
+
+impl FromStr for ScrollEnd {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(ScrollEnd::Stop),
+            "loop" => Ok(ScrollEnd::Loop),
+            _ => Err(format!("unknown --scroll-end behavior: {s}")),
+        }
     }
+}
 
-    lines
+/// First visible row after scrolling `rows` rows through `total` rows of content in a
+/// pane showing `visible` of them.
+fn scroll_offset(rows: usize, total: usize, visible: usize, end: ScrollEnd) -> usize {
+    let max_offset = total.saturating_sub(visible);
+    match end {
+        ScrollEnd::Stop => rows.min(max_offset),
+        ScrollEnd::Loop => rows % (max_offset + 1),
+    }
 }
 
+/// How long to wait for input at a time once playback has finished and the last frame
+/// is being held.
+const HOLD_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Factor each `+` / `-` changes the playback speed by, and how far it can go either way.
+const SPEED_STEP: f64 = 1.25;
+const SPEED_LIMIT: f64 = 16.0;
+
+/// Shortest time any frame stays up, so playback always moves forward in time.
+const MIN_PLAYBACK_DELAY: Duration = Duration::from_millis(1);
+
+/// Shortest time a frame of a loop under `--min-loop-frames` frames stays up, so a
+/// handful of frames can't flicker.
+const SHORT_LOOP_MIN_DELAY: Duration = Duration::from_millis(200);
+
+/// Tempos `--bpm` accepts, so a beat's length is always a representable delay.
+const MIN_BPM: f64 = 1.0;
+const MAX_BPM: f64 = 1000.0;
+
 /// Runs the TUI loop with ~60 fps. Press `q` to quit.
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, frames: &[BrailleFrame<'static>]) -> io::Result<()> {
-    // ~16 ms per frame => ~60 fps
-    let frame_delay = Duration::from_millis(96);
-    let mut frame_index = 0;
-    let mut frame_start = Instant::now();
+///
+/// With `compare`, both animations play side by side, each looping over its own frames.
+/// With `--bpm`, the frame delay is chosen so one loop lasts exactly `--beats` beats;
+/// `--ease` then redistributes that time across the loop. After `plays` loops (`None`
+/// loops forever) the last frame is held, or the app exits with `--on-finish exit`. A
+/// single frame (e.g. a still or a GIF deduplicated down to one) is drawn once and held
+/// until a key is pressed.
+///
+/// Playback starts from `playback`. When the terminal is resized to a different pane
+/// size, the current state is handed back in `AppExit::Resize` to be resumed once the
+/// frames are converted for the new size.
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    main: (String, &[BrailleFrame<'static>]),
+    compare: Option<(String, &[BrailleFrame<'static>])>,
+    plays: Option<u32>,
+    playback: Playback,
+    opts: &Options,
+) -> io::Result<AppExit> {
+    let (main_title, frames) = main;
+    let Playback { mut paused, mut speed, mut color_mode, mut peaking, .. } = playback;
+    // Frames as shown: the truecolor frames, re-encoded while the color mode (from
+    // `--colors`, then `c`) is anything else
+    let recolor = |color_mode: ColorMode| {
+        (color_mode != opts.convert.color_mode).then(|| {
+            (
+                recolor_frames(frames, color_mode),
+                compare.as_ref().map(|(_, frames)| recolor_frames(frames, color_mode)),
+            )
+        })
+    };
+    let mut recolored = recolor(color_mode);
+    let delays = playback_delays(frames, opts);
+    let loop_duration: Duration = delays.iter().sum();
+    // The pane size the frames were converted for
+    let cells = pane_cells(opts);
+    // Reconverting can change the frame count (e.g. `--detect-loop`), so stay in range
+    let mut frame_index = playback.frame.min(frames.len() - 1);
+    let mut compare_index = compare.as_ref().map_or(0, |(_, frames)| playback.compare_frame.min(frames.len() - 1));
+    let mut loops_done = playback.loops_done;
+    // Nothing to animate: hold the frame from the start instead of looping over it
+    let mut finished = playback.finished || (frames.len() == 1 && compare.as_ref().is_none_or(|(_, frames)| frames.len() == 1));
+    // Frames follow a fixed timeline (start + cumulative delays) rather than restarting the
+    // clock on each advance, so drawing and input handling never add up to drift
+    let playback_start = Instant::now();
+    let mut next_frame_at = playback_start + delays[frame_index].div_f64(speed);
+    // When the current frame came up, and the main and compare frames it replaced
+    let mut transition: Option<(Instant, usize, usize)> = None;
+
+    // `--title-format` if given, otherwise the fixed title (or file name when comparing)
+    // (the compare pane advances in step with the main one, so its loop runs at the same pace)
+    let title = |name: &str, default: &str, index: usize, count: usize| match &opts.title_format {
+        Some(template) => {
+            let duration = loop_duration.mul_f64(count as f64 / frames.len() as f64);
+            format_title(
+                template,
+                &TitleVars {
+                    name,
+                    frame: index + 1,
+                    total: count,
+                    elapsed: playback_start.elapsed(),
+                    duration,
+                    fps: count as f64 / duration.as_secs_f64().max(f64::EPSILON),
+                },
+            )
+        }
+        None => default.to_string(),
+    };
 
     loop {
-        // 1) Draw current frame
+        // 1) Draw current frame(s); the first one goes out before anything waits on input
+        // or the clock
+        let (shown, shown_compare) = match &recolored {
+            Some((main, compare)) => (main.as_slice(), compare.as_deref()),
+            None => (frames, compare.as_ref().map(|(_, frames)| *frames)),
+        };
+        // Default titles note the color mode while it isn't truecolor, and the tile
+        let mut mode_tag = match color_mode {
+            ColorMode::Truecolor => String::new(),
+            mode => format!(" [{}]", mode.name()),
+        };
+        if let Some(grid) = opts.tile {
+            mode_tag += &format!(" [tile {}/{}]", opts.tile_index + 1, tile_count(grid));
+        }
+        if speed != 1.0 {
+            mode_tag += &format!(" [x{speed:.2}]");
+        }
+        if paused {
+            mode_tag += " [paused]";
+        }
+        let scrolled = opts.scroll_v.map_or(0, |rows_per_sec| (playback_start.elapsed().as_secs_f64() * rows_per_sec) as usize);
+        let scroll = |area: Rect, frame: &BrailleFrame<'static>| {
+            scroll_offset(scrolled, frame.lines.len(), area.height.saturating_sub(2) as usize, opts.scroll_end)
+        };
+        // With `--crossfade`, colors dissolve from the previous frame for a moment after
+        // each frame boundary
+        let fade = transition.and_then(|(at, main_prev, compare_prev)| {
+            let t = at.elapsed().as_secs_f64() / opts.crossfade?.as_secs_f64();
+            (t < 1.0).then_some((t as f32, main_prev, compare_prev))
+        });
+        let blended = fade.map(|(t, main_prev, compare_prev)| {
+            (
+                blend_frames(&frames[main_prev], &frames[frame_index], t, color_mode),
+                compare.as_ref().map(|(_, frames)| blend_frames(&frames[compare_prev], &frames[compare_index], t, color_mode)),
+            )
+        });
+        let main_frame = blended.as_ref().map_or(&shown[frame_index], |(main, _)| main);
+        let compare_frame = shown_compare.map(|frames| {
+            blended.as_ref().and_then(|(_, compare)| compare.as_ref()).unwrap_or(&frames[compare_index])
+        });
+        // `--crt` goes over everything else, fades included; the band's position follows
+        // the loop
+        let band = opts.crt_band.then(|| {
+            let loop_secs = loop_duration.as_secs_f64().max(f64::EPSILON);
+            (playback_start.elapsed().as_secs_f64() % loop_secs / loop_secs) as f32
+        });
+        let crt = opts.crt.map(|intensity| {
+            (
+                crt_frame(main_frame, intensity, band, color_mode),
+                compare_frame.map(|frame| crt_frame(frame, intensity, band, color_mode)),
+            )
+        });
+        let main_frame = crt.as_ref().map_or(main_frame, |(main, _)| main);
+        let compare_frame = crt.as_ref().map_or(compare_frame, |(_, compare)| compare.as_ref());
+
+        let title_alignment = if opts.convert.rtl { Alignment::Right } else { Alignment::Left };
         terminal.draw(|f| {
             let size = f.area(); // use .area() over .size()
-            let block = Block::default().borders(Borders::ALL).title("GIF - Braille (Hi-Qual)");
-            let current_frame = &frames[frame_index];
-
-            let paragraph = Paragraph::new(current_frame.lines.clone()).block(block);
-            f.render_widget(paragraph, size);
+            match (&compare, compare_frame) {
+                (Some((compare_title, compare_frames)), Some(compare_frame)) => {
+                    let [left, right] = Layout::horizontal([Constraint::Percentage(50); 2]).areas(size);
+                    let left_title = title(&main_title, &format!("{main_title}{mode_tag}"), frame_index, frames.len());
+                    let right_title = title(compare_title, compare_title, compare_index, compare_frames.len());
+                    render_pane(f, left, &left_title, main_frame, peaking, scroll(left, main_frame), title_alignment);
+                    render_pane(f, right, &right_title, compare_frame, peaking, scroll(right, compare_frame), title_alignment);
+                }
+                _ => {
+                    let default = format!("GIF - Braille (Hi-Qual){mode_tag}");
+                    let title = title(&main_title, &default, frame_index, frames.len());
+                    render_pane(f, size, &title, main_frame, peaking, scroll(size, main_frame), title_alignment);
+                }
+            }
         })?;
 
         // 2) Check for user input
-        let elapsed = frame_start.elapsed();
-        let time_left = frame_delay.saturating_sub(elapsed);
+        let time_left = if finished || paused {
+            // Holding a frame: nothing to do until the user presses a key
+            HOLD_POLL_INTERVAL
+        } else {
+            next_frame_at.saturating_duration_since(Instant::now())
+        };
+        // Scrolling keeps moving even while a frame (or a still image) is held
+        let time_left = match opts.scroll_v {
+            Some(rows_per_sec) => match Duration::try_from_secs_f64(1.0 / rows_per_sec) {
+                Ok(row_time) => time_left.min(row_time.max(SCROLL_MIN_TICK)),
+                // So slow a row takes longer than any `Duration`: nothing to scroll yet
+                Err(_) => time_left,
+            },
+            None => time_left,
+        };
+        // Keep redrawing while a crossfade is under way
+        let time_left = if fade.is_some() { time_left.min(CROSSFADE_TICK) } else { time_left };
+        let time_left = if band.is_some() { time_left.min(CRT_TICK) } else { time_left };
 
         if event::poll(time_left)? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') {
-                    return Ok(());
+            // Drain everything that queued up (e.g. a burst of resizes while dragging the
+            // window) so we redraw once for the latest size instead of once per event
+            let mut resized = false;
+            loop {
+                match event::read()? {
+                    Event::Key(key) if key.code == KeyCode::Char('q') => return Ok(AppExit::Quit),
+                    // Previous / next tile in reading order
+                    Event::Key(key) if matches!(key.code, KeyCode::Char('[' | ']')) && opts.tile.is_some() => {
+                        let count = opts.tile.map_or(1, tile_count);
+                        let step = if key.code == KeyCode::Char(']') { 1 } else { count - 1 };
+                        let state = Playback {
+                            frame: frame_index,
+                            compare_frame: compare_index,
+                            paused,
+                            speed,
+                            color_mode,
+                            peaking,
+                            loops_done,
+                            finished,
+                        };
+                        return Ok(AppExit::Tile((opts.tile_index + step) % count, state));
+                    }
+                    Event::Key(key) if key.code == KeyCode::Char('f') => peaking = !peaking,
+                    Event::Key(key) if key.code == KeyCode::Char('c') => {
+                        color_mode = color_mode.next();
+                        recolored = recolor(color_mode);
+                    }
+                    Event::Key(key) if key.code == KeyCode::Char(' ') => {
+                        paused = !paused;
+                        // Resume with the whole current frame still to go
+                        next_frame_at = Instant::now() + delays[frame_index].div_f64(speed);
+                    }
+                    // Stepping wraps around both ways
+                    Event::Key(key) if matches!(key.code, KeyCode::Left | KeyCode::Right) && paused => {
+                        let step = |index: usize, count: usize| match key.code {
+                            KeyCode::Right => (index + 1) % count,
+                            _ => (index + count - 1) % count,
+                        };
+                        frame_index = step(frame_index, frames.len());
+                        if let Some((_, compare_frames)) = &compare {
+                            compare_index = step(compare_index, compare_frames.len());
+                        }
+                        transition = None;
+                    }
+                    Event::Key(key) if matches!(key.code, KeyCode::Char('+' | '=' | '-')) => {
+                        speed = match key.code {
+                            KeyCode::Char('-') => speed / SPEED_STEP,
+                            _ => speed * SPEED_STEP,
+                        }
+                        .clamp(1.0 / SPEED_LIMIT, SPEED_LIMIT);
+                        next_frame_at = Instant::now() + delays[frame_index].div_f64(speed);
+                    }
+                    Event::Resize(..) => resized = true,
+                    // Pasted text is never taken for key commands
+                    Event::Paste(_) => {}
+                    _ => {}
+                }
+                if !event::poll(Duration::ZERO)? {
+                    break;
+                }
+            }
+            if resized {
+                terminal.autoresize()?;
+                if pane_cells(opts) != cells {
+                    return Ok(AppExit::Resize(Playback {
+                        frame: frame_index,
+                        compare_frame: compare_index,
+                        paused,
+                        speed,
+                        color_mode,
+                        peaking,
+                        loops_done,
+                        finished,
+                    }));
                 }
             }
         }
 
-        // 3) Next frame if we've passed ~16 ms
-        if frame_start.elapsed() >= frame_delay {
+        // 3) Advance to whichever frame is due now, skipping any we fell behind on
+        while !finished && !paused && Instant::now() >= next_frame_at {
+            if frame_index + 1 == frames.len() {
+                loops_done += 1;
+                if plays.is_some_and(|plays| loops_done >= plays) {
+                    match opts.on_finish {
+                        OnFinish::Exit => return Ok(AppExit::Quit),
+                        OnFinish::Hold => finished = true,
+                    }
+                    break;
+                }
+            }
+            transition = Some((next_frame_at, frame_index, compare_index));
             frame_index = (frame_index + 1) % frames.len();
-            frame_start = Instant::now();
+            if let Some((_, compare_frames)) = &compare {
+                compare_index = (compare_index + 1) % compare_frames.len();
+            }
+            next_frame_at += delays[frame_index].div_f64(speed);
+            if opts.pause_at.as_ref().is_some_and(|frames| frames.contains(frame_index)) {
+                paused = true;
+            }
+        }
+    }
+}
+
+/// How long each frame stays up during playback: the frames' own delays, rescaled by
+/// `--bpm` so one loop lasts exactly `--beats` beats, then warped by `--ease`. Loops
+/// shorter than `--min-loop-frames` never go below `SHORT_LOOP_MIN_DELAY` per frame,
+/// unless `--bpm` sets the loop length, which has to stay on the beat.
+fn playback_delays(frames: &[BrailleFrame<'static>], opts: &Options) -> Vec<Duration> {
+    let mut delays = timeline_delays(frames, opts);
+    if frames.len() < opts.min_loop_frames && opts.bpm.is_none() {
+        for delay in &mut delays {
+            *delay = (*delay).max(SHORT_LOOP_MIN_DELAY);
+        }
+    }
+    delays
+}
+
+fn timeline_delays(frames: &[BrailleFrame<'static>], opts: &Options) -> Vec<Duration> {
+    let mut delays: Vec<Duration> = frames.iter().map(|frame| frame.delay).collect();
+    let natural: Duration = delays.iter().sum();
+
+    let total = match opts.bpm {
+        Some(bpm) => Duration::from_secs_f64(60.0 / bpm * opts.beats as f64),
+        None => natural,
+    };
+    if natural.is_zero() || (total == natural && opts.ease == Ease::None) {
+        // Even unchanged, a 0 ms delay (e.g. from a crafted cache) would stall playback
+        for delay in &mut delays {
+            *delay = (*delay).max(MIN_PLAYBACK_DELAY);
+        }
+        return delays;
+    }
+
+    // Map each frame's start and end on the original timeline through the easing curve.
+    // Delays are differences of those points, so they add up to exactly `total`.
+    let at = |time: Duration| {
+        if time >= natural {
+            total
+        } else {
+            total.mul_f64(opts.ease.time_at(time.as_secs_f64() / natural.as_secs_f64()))
+        }
+    };
+    let mut start = Duration::ZERO;
+    for delay in &mut delays {
+        let end = start + *delay;
+        *delay = at(end).saturating_sub(at(start)).max(MIN_PLAYBACK_DELAY);
+        start = end;
+    }
+    delays
+}
+
+/// Values available to `--title-format` placeholders.
+struct TitleVars<'a> {
+    name: &'a str,
+    /// 1-based index of the frame on screen.
+    frame: usize,
+    total: usize,
+    elapsed: Duration,
+    /// Length of one loop.
+    duration: Duration,
+    fps: f64,
+}
+
+/// Placeholders understood by `format_title`.
+const TITLE_PLACEHOLDERS: [&str; 6] = ["name", "frame", "total", "time", "duration", "fps"];
+
+/// Substitutes `{placeholder}`s in `template`; unknown ones are left as written.
+fn format_title(template: &str, vars: &TitleVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let key = &rest[start + 1..start + len];
+        match key {
+            "name" => out.push_str(vars.name),
+            "frame" => out.push_str(&vars.frame.to_string()),
+            "total" => out.push_str(&vars.total.to_string()),
+            "time" => out.push_str(&format!("{:.1}s", vars.elapsed.as_secs_f64())),
+            "duration" => out.push_str(&format!("{:.1}s", vars.duration.as_secs_f64())),
+            "fps" => out.push_str(&format!("{:.1}", vars.fps)),
+            _ => out.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Placeholders in `template` that `format_title` won't substitute.
+fn unknown_placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(key, _)| key))
+        .filter(|key| !TITLE_PLACEHOLDERS.contains(key))
+        .collect()
+}
+
+/// Color of the cells highlighted by `--focus-peak`.
+const FOCUS_PEAK_COLOR: Color = Color::Rgb(255, 0, 0);
+
+/// Draws one braille frame inside a titled, bordered block.
+fn render_pane(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    frame: &BrailleFrame<'static>,
+    peaking: bool,
+    scroll: usize,
+    title_alignment: Alignment,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string())
+        .title_alignment(title_alignment);
+    let mut lines = frame.lines.clone();
+    if peaking {
+        for &(row, col) in &frame.peaks {
+            if let Some(span) = lines.get_mut(row).and_then(|line| line.spans.get_mut(col)) {
+                span.style.fg = Some(FOCUS_PEAK_COLOR);
+            }
+        }
+    }
+    let paragraph = Paragraph::new(lines).block(block).scroll((scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The six 200x200 px, 100 ms frames the library tests use too.
+    fn fixture_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/anim.webp")
+    }
+
+    fn fixture_frames() -> Vec<RgbaImage> {
+        SourceFrames::open(&fixture_path(), DEFAULT_MAX_FRAME_DIM).unwrap().map(|frame| frame.unwrap().0).collect()
+    }
+
+    /// A per-process path in the temp dir, so parallel test runs don't collide.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gif_braille_tui_{}_{name}", std::process::id()))
+    }
+
+    #[test]
+    fn size_falls_back_without_a_tty() {
+        let no_tty = Err(io::Error::other("not a terminal"));
+        assert_eq!(size_or_default(no_tty), FALLBACK_TERM_SIZE);
+        assert_eq!(size_or_default(Ok((0, 0))), FALLBACK_TERM_SIZE);
+        assert_eq!(size_or_default(Ok((120, 40))), (120, 40));
+    }
+
+    #[test]
+    fn inline_playback_keeps_to_its_rows() {
+        let inline = Options { term_size: Some((82, 40)), inline_cursor: true, ..Options::default() };
+        assert_eq!(pane_cells(&inline), (80, DEFAULT_INLINE_ROWS));
+        // Never more than the terminal has room for
+        assert_eq!(pane_cells(&Options { term_size: Some((82, 10)), inline_rows: 30, ..inline }), (80, 8));
+    }
+
+    #[test]
+    fn tile_grids_too_big_to_count_are_rejected() {
+        let parse = |grid: &str| parse_args(["--tile", grid, "a.gif"].into_iter().map(String::from)).map(|opts| opts.tile);
+        assert!(parse("70000x70000").is_err());
+        assert_eq!(parse("3x2"), Ok(Some((3, 2))));
+    }
+
+    /// Writes a one-frame GIF of the given size to a temp file and returns its path.
+    fn synthetic_gif(name: &str, width: u32, height: u32) -> PathBuf {
+        let path = temp_path(&format!("{name}.gif"));
+        let image = RgbaImage::from_fn(width, height, |x, y| Rgba([(x % 256) as u8, (y % 256) as u8, 200, 255]));
+        let mut encoder = image::codecs::gif::GifEncoder::new(File::create(&path).unwrap());
+        encoder.encode_frame(image::Frame::new(image)).unwrap();
+        path
+    }
+
+    #[test]
+    fn extract_frames_writes_the_selected_frames() {
+        let range: FrameRange = "1,3-4".parse().unwrap();
+        assert_eq!((range.last(), "2,5-".parse::<FrameRange>().unwrap().last()), (Some(4), None));
+        let written = |path: &Path, range: &FrameRange| {
+            let out_dir = temp_path(&format!("extract_{}", path.file_stem().unwrap().to_string_lossy()));
+            extract_frames(path, range, &out_dir, &Options::default()).unwrap();
+            let mut names: Vec<_> = fs::read_dir(&out_dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+            fs::remove_dir_all(&out_dir).unwrap();
+            names.sort();
+            names
+        };
+        assert_eq!(written(&fixture_path(), &range), ["frame_0001.png", "frame_0003.png", "frame_0004.png"]);
+
+        // A still image is frame 0
+        let still = temp_path("still.png");
+        RgbaImage::from_pixel(8, 8, Rgba([255, 0, 0, 255])).save(&still).unwrap();
+        let names = written(&still, &"0-".parse().unwrap());
+        fs::remove_file(&still).unwrap();
+        assert_eq!(names, ["frame_0000.png"]);
+    }
+
+    #[test]
+    fn aspect_ratio_is_preserved_end_to_end() {
+        // 82x26 leaves 80x24 cells inside the border, i.e. at most 160x96 px
+        let opts = Options { term_size: Some((82, 26)), ..Options::default() };
+        let grid = |path: &Path| {
+            let (frames, _) = load_and_convert_gif(path, &opts).unwrap();
+            fs::remove_file(path).unwrap();
+            let lines = &frames[0].lines;
+            (lines.iter().map(|line| line.spans.len()).max().unwrap(), lines.len())
+        };
+
+        // 4:1 wide, limited by width: 160x40 px => 80x10 cells
+        assert_eq!(grid(&synthetic_gif("wide", 400, 100)), (80, 10));
+        // 1:4 tall, limited by height: 24x96 px => 12x24 cells
+        assert_eq!(grid(&synthetic_gif("tall", 100, 400)), (12, 24));
+        // Smaller than the pane: never upscaled, 30x20 px => 15x5 cells
+        assert_eq!(grid(&synthetic_gif("small", 30, 20)), (15, 5));
+    }
+
+    #[test]
+    fn portrait_fill_moves_focus_peaks_with_the_content() {
+        // 1:4 tall in 80x24 cells is 12x24 cells, centered 34 cells in
+        let opts = Options {
+            term_size: Some((82, 26)),
+            portrait_fill: Some(PortraitFill::Center),
+            focus_peak: Some(10.0),
+            ..Options::default()
+        };
+        let path = synthetic_gif("peaks", 100, 400);
+        let (frames, _) = load_and_convert_gif(&path, &opts).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(!frames[0].peaks.is_empty());
+        assert!(frames[0].peaks.iter().all(|&(_, col)| (34..46).contains(&col)));
+    }
+
+    #[test]
+    fn thin_frames_are_clamped_or_skipped() {
+        // 1000x1 fits 160x96 px as 160x0.16, so its height is clamped to 1 px
+        let path = synthetic_gif("thin", 1000, 1);
+        let (clamped, clamped_count) = load_and_convert_gif(&path, &Options { term_size: Some((82, 26)), ..Options::default() }).unwrap();
+        let skip = Options { term_size: Some((82, 26)), thin_frames: ThinFrames::Skip, ..Options::default() };
+        let (skipped, skipped_count) = load_and_convert_gif(&path, &skip).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(clamped.len(), 1);
+        assert_eq!((clamped[0].lines[0].spans.len(), clamped[0].lines.len()), (80, 1));
+        assert_eq!((clamped_count, skipped_count), (1, 1));
+        assert!(skipped.is_empty());
+    }
+
+    /// A one-cell frame lighting `dots`, so frame differences only count flipped dots.
+    fn dots_frame(dots: u8) -> BrailleFrame<'static> {
+        let cell = Span::styled(braille_glyph(dots).to_string(), Style::default().fg(Color::White));
+        BrailleFrame { lines: vec![Line::from(cell)], delay: Duration::from_millis(100), peaks: Vec::new() }
+    }
+
+    /// Two neighboring dots walking around all 8 positions: every step, including the one from
+    /// the last frame back to the first, flips exactly 2 dots.
+    fn dots_cycle() -> Vec<BrailleFrame<'static>> {
+        (0..8).map(|i| dots_frame(1 << i | 1 << ((i + 1) % 8))).collect()
+    }
+
+    #[test]
+    fn casts_keep_the_color_kind() {
+        let styles = [
+            Style::default().fg(Color::Rgb(1, 2, 3)).bg(Color::Indexed(208)),
+            Style::default().fg(Color::LightCyan).bg(Color::Red),
+        ];
+        let line = Line::from(styles.map(|style| Span::styled("x", style)).to_vec());
+        let ansi = frame_to_ansi(&BrailleFrame { lines: vec![line], ..dots_frame(0) }, ExportColorspace::Srgb);
+        for escape in ["\x1b[38;2;1;2;3m", "\x1b[48;5;208m", "\x1b[96m", "\x1b[41m"] {
+            assert!(ansi.contains(escape), "{escape:?} missing from {ansi:?}");
+        }
+    }
+
+    #[test]
+    fn short_loops_stay_on_the_beat() {
+        // 3 frames at 120 bpm: one 500 ms loop, under the 200 ms short-loop minimum per frame
+        let frames: Vec<_> = (0..3).map(dots_frame).collect();
+        let opts = Options { bpm: Some(120.0), ..Options::default() };
+        assert!(playback_delays(&frames, &opts).iter().all(|&delay| delay < SHORT_LOOP_MIN_DELAY));
+        assert!(parse_args(["--bpm", "1e-300", "a.gif"].into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn eased_loops_keep_their_length() {
+        let frames: Vec<_> = [70, 250, 100, 130].into_iter().map(|ms| BrailleFrame { delay: Duration::from_millis(ms), ..dots_frame(0) }).collect();
+        // Without --bpm the loop keeps its 550 ms; 3 beats at 120 bpm make it 1.5 s
+        for (bpm, length) in [(None, 550), (Some(120.0), 1500)] {
+            for ease in [Ease::In, Ease::Out, Ease::InOut] {
+                let opts = Options { ease, bpm, beats: 3, ..Options::default() };
+                assert_eq!(timeline_delays(&frames, &opts).iter().sum::<Duration>(), Duration::from_millis(length));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_delays_still_move_playback_forward() {
+        let frames: Vec<_> = (0..3).map(|i| BrailleFrame { delay: Duration::ZERO, ..dots_frame(i) }).collect();
+        // No short-loop minimum to hide behind
+        let opts = Options { min_loop_frames: 0, ..Options::default() };
+        assert!(playback_delays(&frames, &opts).iter().all(|delay| !delay.is_zero()));
+        assert!(parse_args(["--frame-delay", "2:0", "a.gif"].into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn detect_loop_keeps_a_seamless_file_whole() {
+        assert_eq!(find_loop(&dots_cycle()), (0, 8));
+    }
+
+    #[test]
+    fn detect_loop_cuts_the_intro() {
+        // A full cell first, 6 dots away from every frame of the cycle that follows it
+        let mut frames = vec![dots_frame(0xFF)];
+        frames.extend(dots_cycle());
+        assert_eq!(find_loop(&frames), (1, 9));
+    }
+
+    #[test]
+    fn sprite_sheet_draws_dots_where_the_terminal_does() {
+        // U+2808 is the top right dot only
+        let img = rasterize_frame(&dots_frame(0x08), ExportColorspace::Srgb);
+        let lit = |sub_col: u32, sub_row: u32| img.get_pixel(sub_col * DOT_PX + 1, sub_row * DOT_PX + 1)[0] > 0;
+        assert!(lit(1, 0));
+        assert!(!lit(0, 0) && !lit(0, 3) && !lit(1, 3));
+    }
+
+    fn frame_delays(path: &Path) -> Vec<Duration> {
+        let opts = Options { term_size: Some((42, 12)), ..Options::default() };
+        load_and_convert_gif(path, &opts).unwrap().0.iter().map(|frame| frame.delay).collect()
+    }
+
+    #[test]
+    fn gif_delay_is_native() {
+        let path = temp_path("delay.gif");
+        let mut encoder = image::codecs::gif::GifEncoder::new(File::create(&path).unwrap());
+        for (image, ms) in fixture_frames().into_iter().zip([70, 250, 0]) {
+            encoder.encode_frame(image::Frame::from_parts(image, 0, 0, image::Delay::from_numer_denom_ms(ms, 1))).unwrap();
+        }
+        drop(encoder);
+        let delays = frame_delays(&path);
+        fs::remove_file(&path).unwrap();
+        // The unset 0 ms delay plays at 100 ms
+        assert_eq!(delays, [Duration::from_millis(70), Duration::from_millis(250), Duration::from_millis(100)]);
+    }
+
+    #[test]
+    fn apng_delay_is_native() {
+        // fcTL delays are a fraction of a second: 1/3 s and 3/40 s
+        let path = temp_path("delay.png");
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 200, 200);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(2, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        for (image, (num, den)) in fixture_frames().into_iter().zip([(1, 3), (3, 40)]) {
+            writer.set_frame_delay(num, den).unwrap();
+            writer.write_image_data(image.as_raw()).unwrap();
         }
+        writer.finish().unwrap();
+        let delays = frame_delays(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(delays, [Duration::from_nanos(333_333_333), Duration::from_millis(75)]);
+    }
+
+    #[test]
+    fn webp_delay_is_native() {
+        assert_eq!(frame_delays(&fixture_path()), [Duration::from_millis(100); 6]);
+    }
+
+    #[test]
+    fn cache_round_trips() {
+        let styles = [
+            Style::default().fg(Color::Rgb(10, 20, 30)).bg(Color::Rgb(40, 50, 60)),
+            Style::default(),
+            Style::default().fg(Color::Indexed(208)).add_modifier(Modifier::DIM),
+            Style::default().fg(Color::LightCyan).bg(Color::Black),
+        ];
+        let line = Line::from(styles.map(|style| Span::styled(braille_glyph(0x5A).to_string(), style)).to_vec());
+        let frames = [BrailleFrame { lines: vec![line.clone(), line], delay: Duration::from_millis(70), peaks: vec![(1, 3)] }];
+        let path = temp_path("round_trip.cache");
+        write_cache(&frames, (4, 2), &path).unwrap();
+        let read = read_cache(&path, (4, 2));
+
+        // Cut off the last frame's peaks
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 12]).unwrap();
+        let truncated = read_cache(&path, (4, 2));
+        fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!((&read[0].lines, read[0].delay, &read[0].peaks), (&frames[0].lines, frames[0].delay, &frames[0].peaks));
+        assert!(truncated.err().unwrap().to_string().contains("truncated"));
+    }
+
+    #[test]
+    fn checksums_are_deterministic() {
+        // Pinned: a change here means every diff against older `--checksums` output breaks
+        assert_eq!(format!("{:016x}", frame_checksum(&dots_frame(0x08))), "704a51911884015f");
+        let frames = fixture_frames();
+        let checksum = |i: usize| frame_checksum(&convert_image(frames[i].clone(), &ConvertOptions::default(), None));
+        assert_ne!(checksum(3), checksum(4));
+    }
+
+    #[test]
+    fn title_placeholders_substitute_or_stay_as_written() {
+        let vars = TitleVars {
+            name: "cat.gif",
+            frame: 3,
+            total: 12,
+            elapsed: Duration::from_millis(1250),
+            duration: Duration::from_secs(2),
+            fps: 6.0,
+        };
+        assert_eq!(format_title("{frame}{total} {time}/{duration} @{fps}", &vars), "312 1.2s/2.0s @6.0");
+        assert_eq!(format_title("{name} {size} {}", &vars), "cat.gif {size} {}");
+        assert_eq!(format_title("{name} {frame", &vars), "cat.gif {frame");
+        assert_eq!(unknown_placeholders("{name}{size} {} {frame"), ["size", ""]);
     }
 }